@@ -0,0 +1,138 @@
+mod report;
+mod runner;
+mod suite;
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use logic::{Agent, BfsFollower, DStarAgent, RandomAgent, WallFollower};
+use tracing::info_span;
+use tracing_subscriber::EnvFilter;
+
+use report::{
+    summarize, write_episodes_csv, write_heatmaps, write_summary_csv, write_summary_markdown,
+};
+use runner::run_episode;
+use suite::{MazeSpec, build_suite};
+
+const DEFAULT_MAZES_PER_CONFIG: usize = 10;
+const DEFAULT_EPISODES_PER_MAZE: usize = 1;
+const DEFAULT_MAX_STEPS: usize = 10_000;
+const DEFAULT_OUTPUT_DIR: &str = "results";
+
+// Takes the episode number so repeated runs of a random agent differ
+type AgentFactory = fn(&MazeSpec, usize) -> Box<dyn Agent>;
+
+const AGENTS: [AgentFactory; 5] = [
+    |maze, episode| Box::new(RandomAgent::new(maze.seed.wrapping_add(episode as u64))),
+    |_, _| Box::new(WallFollower::default()),
+    |_, _| Box::new(BfsFollower::default()),
+    |_, _| Box::new(DStarAgent::new(false)),
+    |_, _| Box::new(DStarAgent::new(true)),
+];
+
+struct Args {
+    mazes_per_config: usize,
+    episodes_per_maze: usize,
+    max_steps: usize,
+    output_dir: PathBuf,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args {
+        mazes_per_config: DEFAULT_MAZES_PER_CONFIG,
+        episodes_per_maze: DEFAULT_EPISODES_PER_MAZE,
+        max_steps: DEFAULT_MAX_STEPS,
+        output_dir: PathBuf::from(DEFAULT_OUTPUT_DIR),
+    };
+
+    let mut raw = env::args().skip(1);
+    while let Some(flag) = raw.next() {
+        let value = raw
+            .next()
+            .ok_or_else(|| format!("missing value for {flag}"))?;
+        match flag.as_str() {
+            "--mazes" => {
+                args.mazes_per_config = value
+                    .parse()
+                    .map_err(|_| format!("invalid maze count: {value}"))?
+            }
+            "--episodes" => {
+                args.episodes_per_maze = value
+                    .parse()
+                    .map_err(|_| format!("invalid episode count: {value}"))?
+            }
+            "--max-steps" => {
+                args.max_steps = value
+                    .parse()
+                    .map_err(|_| format!("invalid step limit: {value}"))?
+            }
+            "--output" => args.output_dir = PathBuf::from(value),
+            _ => return Err(format!("unknown flag: {flag}")),
+        }
+    }
+
+    Ok(args)
+}
+
+fn main() -> ExitCode {
+    // Episode events are too chatty for a full suite, set RUST_LOG to see them
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .with_writer(std::io::stderr)
+        .init();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{error}");
+            eprintln!("usage: harness [--mazes N] [--episodes N] [--max-steps N] [--output DIR]");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let suite = build_suite(args.mazes_per_config);
+    let mut results = Vec::new();
+    for factory in AGENTS {
+        for maze in &suite {
+            for episode in 0..args.episodes_per_maze {
+                let mut agent = factory(maze, episode);
+                let _span = info_span!(
+                    "episode",
+                    agent = agent.name(),
+                    generator = maze.generator.name(),
+                    seed = maze.seed,
+                    episode,
+                )
+                .entered();
+                results.push(run_episode(maze, agent.as_mut(), args.max_steps));
+            }
+        }
+    }
+
+    let summary = summarize(&results);
+    let written = fs::create_dir_all(&args.output_dir)
+        .and_then(|_| write_episodes_csv(&args.output_dir.join("episodes.csv"), &results))
+        .and_then(|_| write_summary_csv(&args.output_dir.join("summary.csv"), &summary))
+        .and_then(|_| write_summary_markdown(&args.output_dir.join("summary.md"), &summary))
+        .and_then(|_| write_heatmaps(&args.output_dir.join("heatmaps"), &results));
+
+    if let Err(error) = written {
+        eprintln!(
+            "failed to write results to {}: {error}",
+            args.output_dir.display()
+        );
+        return ExitCode::FAILURE;
+    }
+
+    println!(
+        "Ran {} episodes, results in {}",
+        results.len(),
+        args.output_dir.display()
+    );
+    ExitCode::SUCCESS
+}
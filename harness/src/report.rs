@@ -0,0 +1,190 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Result, Write};
+use std::path::Path;
+
+use logic::Grid;
+
+use crate::runner::EpisodeResult;
+
+#[derive(Debug)]
+pub struct SummaryRow {
+    pub agent: &'static str,
+    pub generator: &'static str,
+    pub size: String,
+    pub episodes: usize,
+    pub success_rate: f64,
+    pub mean_steps: f64,
+    // Only successful episodes have a meaningful ratio
+    pub mean_optimal_ratio: Option<f64>,
+    pub mean_wall_bumps: f64,
+    pub mean_micros_per_step: f64,
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), value| (sum + value, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+pub fn summarize(results: &[EpisodeResult]) -> Vec<SummaryRow> {
+    let mut groups: Vec<(&'static str, &'static str, String, Vec<&EpisodeResult>)> = Vec::new();
+
+    for result in results {
+        let generator = result.maze.generator.name();
+        let size = format!("{}x{}", result.maze.width, result.maze.height);
+        match groups
+            .iter_mut()
+            .find(|(agent, g, s, _)| *agent == result.agent && *g == generator && *s == size)
+        {
+            Some((_, _, _, episodes)) => episodes.push(result),
+            None => groups.push((result.agent, generator, size, vec![result])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(agent, generator, size, episodes)| {
+            let successes = episodes.iter().filter(|episode| episode.success);
+
+            SummaryRow {
+                agent,
+                generator,
+                size,
+                episodes: episodes.len(),
+                success_rate: successes.clone().count() as f64 / episodes.len() as f64,
+                mean_steps: mean(episodes.iter().map(|episode| episode.steps as f64))
+                    .unwrap_or(0.0),
+                mean_optimal_ratio: mean(successes.map(|episode| episode.optimal_ratio())),
+                mean_wall_bumps: mean(episodes.iter().map(|episode| episode.wall_bumps as f64))
+                    .unwrap_or(0.0),
+                mean_micros_per_step: mean(
+                    episodes
+                        .iter()
+                        .map(|episode| episode.time_per_step().as_secs_f64() * 1_000_000.0),
+                )
+                .unwrap_or(0.0),
+            }
+        })
+        .collect()
+}
+
+pub fn write_episodes_csv(path: &Path, results: &[EpisodeResult]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        "agent,generator,width,height,seed,success,steps,optimal_steps,optimal_ratio,wall_bumps,micros_per_step"
+    )?;
+
+    for result in results {
+        writeln!(
+            file,
+            "{},{},{},{},{},{},{},{},{:.4},{},{:.3}",
+            result.agent,
+            result.maze.generator.name(),
+            result.maze.width,
+            result.maze.height,
+            result.maze.seed,
+            result.success,
+            result.steps,
+            result.maze.optimal_steps,
+            result.optimal_ratio(),
+            result.wall_bumps,
+            result.time_per_step().as_secs_f64() * 1_000_000.0,
+        )?;
+    }
+
+    file.flush()
+}
+
+pub fn write_summary_csv(path: &Path, rows: &[SummaryRow]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        "agent,generator,size,episodes,success_rate,mean_steps,mean_optimal_ratio,mean_wall_bumps,mean_micros_per_step"
+    )?;
+
+    for row in rows {
+        writeln!(
+            file,
+            "{},{},{},{},{:.4},{:.2},{},{:.2},{:.3}",
+            row.agent,
+            row.generator,
+            row.size,
+            row.episodes,
+            row.success_rate,
+            row.mean_steps,
+            row.mean_optimal_ratio
+                .map(|ratio| format!("{ratio:.4}"))
+                .unwrap_or_default(),
+            row.mean_wall_bumps,
+            row.mean_micros_per_step,
+        )?;
+    }
+
+    file.flush()
+}
+
+pub fn write_summary_markdown(path: &Path, rows: &[SummaryRow]) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(
+        file,
+        "| Agent | Generator | Size | Episodes | Success | Mean steps | Steps / optimal | Wall bumps | µs / step |"
+    )?;
+    writeln!(file, "|---|---|---|---:|---:|---:|---:|---:|---:|")?;
+
+    for row in rows {
+        writeln!(
+            file,
+            "| {} | {} | {} | {} | {:.1}% | {:.1} | {} | {:.1} | {:.2} |",
+            row.agent,
+            row.generator,
+            row.size,
+            row.episodes,
+            row.success_rate * 100.0,
+            row.mean_steps,
+            row.mean_optimal_ratio
+                .map(|ratio| format!("{ratio:.2}"))
+                .unwrap_or_else(|| "-".to_string()),
+            row.mean_wall_bumps,
+            row.mean_micros_per_step,
+        )?;
+    }
+
+    file.flush()
+}
+
+// Visit counts summed over every episode an agent played on the same maze, one
+// CSV grid per agent and maze
+pub fn write_heatmaps(dir: &Path, results: &[EpisodeResult]) -> Result<usize> {
+    let mut heatmaps: Vec<(String, Grid<u32>)> = Vec::new();
+
+    for result in results {
+        let name = format!(
+            "{}_{}_{}x{}_seed{}.csv",
+            result.agent,
+            result.maze.generator.name(),
+            result.maze.width,
+            result.maze.height,
+            result.maze.seed,
+        );
+        match heatmaps.iter_mut().find(|(existing, _)| *existing == name) {
+            Some((_, visits)) => {
+                for (position, count) in visits.iter_mut() {
+                    *count += result.visits[position];
+                }
+            }
+            None => heatmaps.push((name, result.visits.clone())),
+        }
+    }
+
+    fs::create_dir_all(dir)?;
+    for (name, visits) in &heatmaps {
+        let mut file = BufWriter::new(File::create(dir.join(name))?);
+        for row in visits.rows() {
+            let row: Vec<String> = row.iter().map(|count| count.to_string()).collect();
+            writeln!(file, "{}", row.join(","))?;
+        }
+        file.flush()?;
+    }
+
+    Ok(heatmaps.len())
+}
@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+use logic::{Agent, Direction, Grid, Outcome, TileType};
+
+use crate::suite::MazeSpec;
+
+#[derive(Debug, Clone)]
+pub struct EpisodeResult {
+    pub agent: &'static str,
+    pub maze: MazeSpec,
+    pub success: bool,
+    pub steps: usize,
+    pub wall_bumps: usize,
+    pub elapsed: Duration,
+    pub visits: Grid<u32>,
+}
+
+impl EpisodeResult {
+    pub fn optimal_ratio(&self) -> f64 {
+        self.steps as f64 / self.maze.optimal_steps.max(1) as f64
+    }
+
+    pub fn time_per_step(&self) -> Duration {
+        self.elapsed / self.steps.max(1) as u32
+    }
+}
+
+pub fn run_episode(maze: &MazeSpec, agent: &mut dyn Agent, max_steps: usize) -> EpisodeResult {
+    let mut game_state = maze.build();
+    let mut steps = 0;
+    let mut wall_bumps = 0;
+
+    // Planning in reset counts towards the agent's time
+    let start = Instant::now();
+    agent.reset(&game_state);
+
+    while !game_state.is_over() && steps < max_steps {
+        agent.sync(&game_state);
+        let action = agent.act(&game_state.get_observation());
+        let direction = Direction::from(action);
+        // Only walking into a wall or a locked door counts, a conveyor pushing
+        // the agent back to where it was doesn't
+        let player = &game_state.player;
+        let bumps = player.direction == direction
+            && game_state
+                .tiles
+                .neighbour(player.position, direction)
+                .is_none_or(|ahead| match game_state.tiles[ahead].tile_type {
+                    TileType::Wall => true,
+                    TileType::Door(color) => !player.inventory.has(color),
+                    _ => false,
+                });
+
+        game_state.apply_action(action);
+        steps += 1;
+
+        if bumps {
+            wall_bumps += 1;
+        }
+    }
+
+    EpisodeResult {
+        agent: agent.name(),
+        maze: maze.clone(),
+        success: game_state.outcome == Outcome::Success,
+        steps,
+        wall_bumps,
+        elapsed: start.elapsed(),
+        visits: game_state.visits,
+    }
+}
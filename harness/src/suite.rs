@@ -0,0 +1,51 @@
+use logic::{GameState, Generator, optimal_actions};
+
+const GENERATORS: [Generator; 2] = [
+    Generator::Random { wall_density: 0.2 },
+    Generator::Backtracker,
+];
+const SIZES: [(usize, usize); 3] = [(15, 15), (31, 31), (63, 63)];
+
+#[derive(Debug, Clone)]
+pub struct MazeSpec {
+    pub generator: Generator,
+    pub width: usize,
+    pub height: usize,
+    pub seed: u64,
+    pub optimal_steps: usize,
+}
+
+impl MazeSpec {
+    pub fn build(&self) -> GameState {
+        GameState::with_generator(self.width, self.height, self.generator, self.seed)
+    }
+}
+
+// Seeds are tried in order and unsolvable mazes skipped, so the suite is the
+// same on every run.
+pub fn build_suite(mazes_per_config: usize) -> Vec<MazeSpec> {
+    let mut suite = Vec::new();
+
+    for generator in GENERATORS {
+        for (width, height) in SIZES {
+            let mut seed = 0;
+            let mut found = 0;
+            while found < mazes_per_config {
+                let game_state = GameState::with_generator(width, height, generator, seed);
+                if let Some(actions) = optimal_actions(&game_state) {
+                    suite.push(MazeSpec {
+                        generator,
+                        width,
+                        height,
+                        seed,
+                        optimal_steps: actions.len(),
+                    });
+                    found += 1;
+                }
+                seed += 1;
+            }
+        }
+    }
+
+    suite
+}
@@ -0,0 +1,69 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use logic::{Action, GameState, Generator};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+const MAZE_SIZES: [(usize, usize); 5] = [
+    (50, 30),
+    (256, 256),
+    (1024, 1024),
+    (2048, 2048),
+    (4096, 4096),
+];
+const ACTIONS_PER_ITERATION: usize = 1_000;
+
+fn create_maze(c: &mut Criterion) {
+    let mut group = c.benchmark_group("create_maze");
+    group.sample_size(10);
+
+    for (width, height) in MAZE_SIZES {
+        group.throughput(Throughput::Elements((width * height) as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{width}x{height}")),
+            &(width, height),
+            |b, &(width, height)| b.iter(|| GameState::create_maze(width, height)),
+        );
+    }
+
+    group.finish();
+}
+
+fn apply_action(c: &mut Criterion) {
+    let mut rng = StdRng::seed_from_u64(0);
+    let actions: Vec<Action> = (0..ACTIONS_PER_ITERATION)
+        .map(|_| *Action::ALL.choose(&mut rng).unwrap())
+        .collect();
+
+    let mut group = c.benchmark_group("apply_action");
+    group.throughput(Throughput::Elements(ACTIONS_PER_ITERATION as u64));
+
+    for generator in [Generator::default(), Generator::Backtracker] {
+        group.bench_function(generator.name(), |b| {
+            b.iter_batched(
+                || GameState::with_generator(256, 256, generator, 0),
+                |mut game_state| {
+                    for &action in &actions {
+                        game_state.apply_action(black_box(action));
+                    }
+                    game_state
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn get_observation(c: &mut Criterion) {
+    let game_state = GameState::with_generator(256, 256, Generator::default(), 0);
+
+    c.bench_function("get_observation", |b| {
+        b.iter(|| black_box(&game_state).get_observation())
+    });
+}
+
+criterion_group!(benches, create_maze, apply_action, get_observation);
+criterion_main!(benches);
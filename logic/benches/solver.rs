@@ -0,0 +1,123 @@
+use std::hint::black_box;
+
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use logic::{
+    DStarLite, GameState, Generator, Search, SearchAlgorithm, cheapest_actions, optimal_actions,
+};
+
+const MAZE_SIZES: [usize; 3] = [31, 127, 511];
+
+// Every generator at every size, labelled the way the groups report them
+fn mazes() -> Vec<(&'static str, String, GameState)> {
+    let mut mazes = Vec::new();
+    for generator in [Generator::default(), Generator::Backtracker] {
+        for size in MAZE_SIZES {
+            let game_state = GameState::with_generator(size, size, generator, 0);
+            mazes.push((generator.name(), format!("{size}x{size}"), game_state));
+        }
+    }
+    mazes
+}
+
+fn optimal_actions_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("optimal_actions");
+    group.sample_size(20);
+
+    for (generator, size, game_state) in mazes() {
+        group.bench_with_input(
+            BenchmarkId::new(generator, size),
+            &game_state,
+            |b, game_state| b.iter(|| optimal_actions(black_box(game_state))),
+        );
+    }
+
+    group.finish();
+}
+
+fn cheapest_actions_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cheapest_actions");
+    group.sample_size(20);
+
+    for (generator, size, game_state) in mazes() {
+        group.bench_with_input(
+            BenchmarkId::new(generator, size),
+            &game_state,
+            |b, game_state| b.iter(|| cheapest_actions(black_box(game_state))),
+        );
+    }
+
+    group.finish();
+}
+
+fn search_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    group.sample_size(20);
+
+    for (generator, size, game_state) in mazes() {
+        for algorithm in SearchAlgorithm::ALL {
+            group.bench_with_input(
+                BenchmarkId::new(format!("{algorithm}/{generator}"), &size),
+                &game_state,
+                |b, game_state| {
+                    b.iter(|| Search::new(game_state, algorithm).run(black_box(game_state)))
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+fn dstar_lite_bench(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dstar_lite");
+    group.sample_size(20);
+
+    for (generator, size, game_state) in mazes() {
+        let start = game_state.player.position;
+        group.bench_with_input(
+            BenchmarkId::new(format!("plan/{generator}"), &size),
+            &game_state,
+            |b, game_state| {
+                b.iter(|| DStarLite::from_game_state(black_box(game_state)).plan(start))
+            },
+        );
+
+        // Closing a tile halfway along the plan, so only the repair is timed
+        let Some((path, _)) = DStarLite::from_game_state(&game_state).plan(start) else {
+            continue;
+        };
+        let blocked = path[path.len() / 2];
+        if blocked == start || game_state.goals().any(|goal| goal == blocked) {
+            continue;
+        }
+        group.bench_with_input(
+            BenchmarkId::new(format!("replan/{generator}"), &size),
+            &game_state,
+            |b, game_state| {
+                b.iter_batched(
+                    || {
+                        let mut planner = DStarLite::from_game_state(game_state);
+                        planner.plan(start);
+                        planner
+                    },
+                    |mut planner| {
+                        planner.set_cost(blocked, None);
+                        planner.plan(start)
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    optimal_actions_bench,
+    cheapest_actions_bench,
+    search_bench,
+    dstar_lite_bench
+);
+criterion_main!(benches);
@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+use crate::game_state::GameState;
+use crate::grid::Grid;
+use crate::replanner::{DStarLite, step_cost};
+use crate::solver::optimal_actions;
+use crate::types::{
+    Action, Direction, EndCondition, GoalMode, Inventory, Observation, Position, Terrain, TileType,
+};
+
+pub trait Agent {
+    fn name(&self) -> &'static str;
+
+    // Called once before the first action of an episode
+    fn reset(&mut self, _game_state: &GameState) {}
+
+    // Called before every action with the whole game, for agents that are
+    // allowed to know more than they observe
+    fn sync(&mut self, _game_state: &GameState) {}
+
+    fn act(&mut self, observation: &Observation) -> Action;
+}
+
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Agent for RandomAgent {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn act(&mut self, _observation: &Observation) -> Action {
+        *Action::ALL.choose(&mut self.rng).unwrap()
+    }
+}
+
+// Right-hand rule using only what the player can see in front of them
+#[derive(Default)]
+pub struct WallFollower {
+    probe_right: bool,
+}
+
+impl Agent for WallFollower {
+    fn name(&self) -> &'static str {
+        "wall_follower"
+    }
+
+    fn reset(&mut self, _game_state: &GameState) {
+        self.probe_right = false;
+    }
+
+    fn act(&mut self, observation: &Observation) -> Action {
+        if self.probe_right {
+            self.probe_right = false;
+            return observation.direction.turn_right().into();
+        }
+
+        let ahead_open = observation
+            .visible_tiles
+            .first()
+            .is_some_and(|tile_type| !tile_type.blocks(observation.inventory));
+
+        if ahead_open {
+            self.probe_right = true;
+            observation.direction.into()
+        } else {
+            observation.direction.turn_left().into()
+        }
+    }
+}
+
+// Plans on the full map at reset and replays the plan, wall following if there is none
+#[derive(Default)]
+pub struct BfsFollower {
+    plan: VecDeque<Action>,
+    fallback: WallFollower,
+}
+
+impl Agent for BfsFollower {
+    fn name(&self) -> &'static str {
+        "bfs_follower"
+    }
+
+    fn reset(&mut self, game_state: &GameState) {
+        self.plan = optimal_actions(game_state).unwrap_or_default().into();
+        self.fallback.reset(game_state);
+    }
+
+    fn act(&mut self, observation: &Observation) -> Action {
+        match self.plan.pop_front() {
+            Some(action) => action,
+            None => self.fallback.act(observation),
+        }
+    }
+}
+
+// Walks a D* Lite plan and repairs it whenever it sees something it got wrong.
+// Without the full map it starts out assuming every tile is open plain ground
+// and learns the maze only from observations.
+pub struct DStarAgent {
+    full_map: bool,
+    planner: Option<DStarLite>,
+    // Every tile seen so far, so doors can be recosted when a key turns up
+    seen: Grid<Option<(TileType, Terrain)>>,
+    inventory: Inventory,
+    // Goals not reached yet in checkpoint order, and how many were
+    goals: Vec<Position>,
+    goal_mode: GoalMode,
+    goals_reached: usize,
+    // Where items might still lie, dropped once the agent stands on or sees
+    // the tile without one
+    items: Vec<Position>,
+    end_condition: EndCondition,
+    fallback: WallFollower,
+}
+
+impl DStarAgent {
+    pub fn new(full_map: bool) -> Self {
+        DStarAgent {
+            full_map,
+            planner: None,
+            seen: Grid::new(0, 0, None),
+            inventory: Inventory::default(),
+            goals: Vec::new(),
+            goal_mode: GoalMode::default(),
+            goals_reached: 0,
+            items: Vec::new(),
+            end_condition: EndCondition::default(),
+            fallback: WallFollower::default(),
+        }
+    }
+
+    fn targets(&self) -> Vec<Position> {
+        match (self.end_condition, self.goal_mode) {
+            (EndCondition::CollectAll, _) => self.items.clone(),
+            (_, GoalMode::Ordered) => self.goals.iter().take(1).copied().collect(),
+            (_, GoalMode::Any | GoalMode::All) => self.goals.clone(),
+        }
+    }
+
+    fn learn(&mut self, observation: &Observation) {
+        let Some(planner) = &mut self.planner else {
+            return;
+        };
+
+        let items = self.items.len();
+        self.items.retain(|&item| item != observation.position);
+
+        let mut position = observation.position;
+        for (index, (&tile_type, &terrain)) in observation
+            .visible_tiles
+            .iter()
+            .zip(&observation.visible_terrain)
+            .enumerate()
+        {
+            // The ray reports a wall where it leaves the maze
+            let Some(next) = self.seen.neighbour(position, observation.direction) else {
+                break;
+            };
+            position = next;
+            self.seen[position] = Some((tile_type, terrain));
+            planner.set_cost(
+                position,
+                step_cost(tile_type, terrain, observation.inventory),
+            );
+            if !observation
+                .visible_items
+                .iter()
+                .any(|&(seen, _)| seen == index)
+            {
+                self.items.retain(|&item| item != position);
+            }
+        }
+
+        if observation.inventory != self.inventory {
+            self.inventory = observation.inventory;
+            for (position, seen) in self.seen.iter() {
+                if let Some((tile_type @ TileType::Door(_), terrain)) = *seen {
+                    planner.set_cost(position, step_cost(tile_type, terrain, self.inventory));
+                }
+            }
+        }
+
+        // A goal only counts when the tally goes up, so this is the one underfoot
+        let reached = observation.goals_reached > self.goals_reached;
+        if reached {
+            self.goals_reached = observation.goals_reached;
+            self.goals.retain(|&goal| goal != observation.position);
+        }
+        if reached || self.items.len() != items {
+            let targets = self.targets();
+            if let Some(planner) = &mut self.planner {
+                planner.set_goals(targets);
+            }
+        }
+    }
+}
+
+impl Agent for DStarAgent {
+    fn name(&self) -> &'static str {
+        if self.full_map {
+            "dstar_full_map"
+        } else {
+            "dstar"
+        }
+    }
+
+    fn reset(&mut self, game_state: &GameState) {
+        let start = game_state.player.position;
+        self.goals = game_state.goals().collect();
+        self.goal_mode = game_state.goal_mode;
+        self.goals_reached = 0;
+        self.items = game_state
+            .remaining_items(&game_state.player)
+            .map(|(_, position, _)| position)
+            .collect();
+        self.end_condition = game_state.end_condition;
+        if self.full_map {
+            self.planner = Some(DStarLite::from_game_state(game_state));
+            self.seen = game_state
+                .tiles
+                .map(|tile| Some((tile.tile_type, tile.terrain)));
+        } else {
+            self.planner = Some(DStarLite::unknown(
+                game_state.width,
+                game_state.height,
+                start,
+                self.targets(),
+            ));
+            self.seen = Grid::new(game_state.width, game_state.height, None);
+        }
+        self.inventory = game_state.player.inventory;
+        self.fallback.reset(game_state);
+    }
+
+    // Dynamic walls move without being seen, the full map picks them up here
+    fn sync(&mut self, game_state: &GameState) {
+        if self.full_map
+            && let Some(planner) = &mut self.planner
+        {
+            planner.sync(game_state);
+        }
+    }
+
+    fn act(&mut self, observation: &Observation) -> Action {
+        self.learn(observation);
+
+        let position = observation.position;
+        let next = self
+            .planner
+            .as_mut()
+            .and_then(|planner| planner.next_step(position));
+        let direction = next.and_then(|next| {
+            Direction::ALL
+                .into_iter()
+                .find(|&direction| self.seen.neighbour(position, direction) == Some(next))
+        });
+
+        match direction {
+            // Facing another way this turns, which also shows what is there
+            Some(direction) => direction.into(),
+            None => self.fallback.act(observation),
+        }
+    }
+}
@@ -0,0 +1,905 @@
+use std::error::Error;
+use std::fmt;
+
+use rand::prelude::*;
+use rand::rngs::StdRng;
+use tracing::{debug, debug_span, info};
+
+use crate::game_state::GameState;
+use crate::generator::{
+    Generator, place_doors, place_dynamic_walls, place_enemies, place_goals, place_hazards,
+    place_items, place_slippery, place_teleporters, place_terrain,
+};
+use crate::grid::Grid;
+use crate::types::{
+    Direction, EndCondition, GoalMode, Inventory, Item, KeyColor, Outcome, Player, Position, Tile,
+    TileType,
+};
+
+pub const MAX_DIMENSION: usize = 4096;
+pub const DEFAULT_VIEW_RANGE: usize = 3;
+// Map files spell teleporter ids as single digits
+pub const MAX_TELEPORTERS: usize = 10;
+pub const DEFAULT_HEALTH: u32 = 3;
+// Solvers track health as part of the state, so it has to stay small
+pub const MAX_HEALTH: u32 = 9;
+// Visiting every goal makes solvers track which ones are done, one bit each
+pub const MAX_GOALS: usize = 6;
+// Same for items when all of them have to be collected
+pub const MAX_ITEMS: usize = 8;
+// Generated layouts tried before giving up on finding anything to collect
+const MAX_LAYOUT_ATTEMPTS: usize = 100;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    EmptyDimensions {
+        width: usize,
+        height: usize,
+    },
+    TooLarge {
+        width: usize,
+        height: usize,
+        max_width: usize,
+        max_height: usize,
+    },
+    // A maze needs room for a start, its goals and placed items on different tiles
+    TooSmall {
+        width: usize,
+        height: usize,
+    },
+    InvalidWallDensity(f64),
+    OutOfBounds {
+        name: &'static str,
+        position: Position,
+    },
+    OnWall {
+        name: &'static str,
+        position: Position,
+    },
+    StartIsGoal(Position),
+    InvalidViewRange(usize),
+    InvalidStepLimit(usize),
+    TooManyDoors(usize),
+    TooManyTeleporters(usize),
+    UnpairedTeleporter(u8),
+    InvalidHealth(u32),
+    InvalidGoalCount(usize),
+    DuplicateGoal(Position),
+    TooManyItems(usize),
+    // Items go on free walkable tiles, not under the start or a goal
+    ItemMisplaced(Position),
+    NothingToCollect,
+    Parse {
+        line: usize,
+        message: String,
+    },
+    Io(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyDimensions { width, height } => {
+                write!(f, "maze dimensions must be non-zero, got {width}x{height}")
+            }
+            ConfigError::TooLarge {
+                width,
+                height,
+                max_width,
+                max_height,
+            } => write!(
+                f,
+                "maze of {width}x{height} exceeds the maximum of {max_width}x{max_height}"
+            ),
+            ConfigError::TooSmall { width, height } => {
+                write!(
+                    f,
+                    "maze of {width}x{height} has no room for the start, goals and items"
+                )
+            }
+            ConfigError::InvalidWallDensity(density) => {
+                write!(f, "wall density must be between 0 and 1, got {density}")
+            }
+            ConfigError::OutOfBounds { name, position } => {
+                write!(
+                    f,
+                    "{name} ({}, {}) is outside the maze",
+                    position.x, position.y
+                )
+            }
+            ConfigError::OnWall { name, position } => {
+                write!(f, "{name} ({}, {}) is on a wall", position.x, position.y)
+            }
+            ConfigError::StartIsGoal(position) => write!(
+                f,
+                "start and goal are both at ({}, {})",
+                position.x, position.y
+            ),
+            ConfigError::InvalidViewRange(view_range) => {
+                write!(f, "view range must be at least 1, got {view_range}")
+            }
+            ConfigError::InvalidStepLimit(max_steps) => {
+                write!(f, "step limit must be at least 1, got {max_steps}")
+            }
+            ConfigError::TooManyDoors(doors) => write!(
+                f,
+                "at most {} key and door pairs are supported, got {doors}",
+                KeyColor::ALL.len()
+            ),
+            ConfigError::TooManyTeleporters(teleporters) => write!(
+                f,
+                "at most {MAX_TELEPORTERS} teleporter pairs are supported, got {teleporters}"
+            ),
+            ConfigError::UnpairedTeleporter(id) => {
+                write!(f, "teleporter {id} needs exactly two tiles")
+            }
+            ConfigError::InvalidHealth(health) => {
+                write!(f, "health must be between 1 and {MAX_HEALTH}, got {health}")
+            }
+            ConfigError::InvalidGoalCount(goals) => {
+                write!(
+                    f,
+                    "goal count must be between 1 and {MAX_GOALS}, got {goals}"
+                )
+            }
+            ConfigError::DuplicateGoal(position) => {
+                write!(f, "more than one goal at ({}, {})", position.x, position.y)
+            }
+            ConfigError::TooManyItems(items) => {
+                write!(f, "at most {MAX_ITEMS} items are supported, got {items}")
+            }
+            ConfigError::ItemMisplaced(position) => write!(
+                f,
+                "item at ({}, {}) is not on a free walkable tile",
+                position.x, position.y
+            ),
+            ConfigError::NothingToCollect => {
+                write!(f, "collecting every item needs at least one item")
+            }
+            ConfigError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            ConfigError::Io(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+#[derive(Debug, Clone)]
+pub struct GameStateBuilder {
+    width: usize,
+    height: usize,
+    max_width: usize,
+    max_height: usize,
+    generator: Generator,
+    seed: Option<u64>,
+    tiles: Option<Grid<Tile>>,
+    start: Option<Position>,
+    goal: Option<Position>,
+    extra_goals: Vec<Position>,
+    goals: usize,
+    goal_mode: GoalMode,
+    placed_items: Vec<(Position, Item)>,
+    items: usize,
+    end_condition: EndCondition,
+    direction: Direction,
+    view_range: usize,
+    max_steps: Option<usize>,
+    doors: usize,
+    teleporters: usize,
+    terrain: bool,
+    slippery: usize,
+    hazards: usize,
+    hazards_visible: bool,
+    health: u32,
+    enemies: usize,
+    dynamic_walls: usize,
+}
+
+impl Default for GameStateBuilder {
+    fn default() -> Self {
+        GameStateBuilder {
+            width: 50,
+            height: 30,
+            max_width: MAX_DIMENSION,
+            max_height: MAX_DIMENSION,
+            generator: Generator::default(),
+            seed: None,
+            tiles: None,
+            start: None,
+            goal: None,
+            extra_goals: Vec::new(),
+            goals: 1,
+            goal_mode: GoalMode::default(),
+            placed_items: Vec::new(),
+            items: 0,
+            end_condition: EndCondition::default(),
+            direction: Direction::Up,
+            view_range: DEFAULT_VIEW_RANGE,
+            max_steps: None,
+            doors: 0,
+            teleporters: 0,
+            terrain: false,
+            slippery: 0,
+            hazards: 0,
+            hazards_visible: true,
+            health: DEFAULT_HEALTH,
+            enemies: 0,
+            dynamic_walls: 0,
+        }
+    }
+}
+
+impl GameStateBuilder {
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    // Lets front-ends with a smaller canvas tighten the limit
+    pub fn max_size(mut self, max_width: usize, max_height: usize) -> Self {
+        self.max_width = max_width;
+        self.max_height = max_height;
+        self
+    }
+
+    pub fn generator(mut self, generator: Generator) -> Self {
+        self.generator = generator;
+        self
+    }
+
+    pub fn wall_density(self, wall_density: f64) -> Self {
+        self.generator(Generator::Random { wall_density })
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    // Uses a hand-made layout instead of running the generator
+    pub fn tiles(mut self, tiles: Grid<Tile>) -> Self {
+        self.width = tiles.width();
+        self.height = tiles.height();
+        self.tiles = Some(tiles);
+        self
+    }
+
+    pub fn start(mut self, start: Position) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn goal(mut self, goal: Position) -> Self {
+        self.goal = Some(goal);
+        self
+    }
+
+    // More goals for hand-made layouts, checkpoints follow the order given
+    pub fn extra_goals(mut self, extra_goals: Vec<Position>) -> Self {
+        self.extra_goals = extra_goals;
+        self
+    }
+
+    // How many goals generated mazes get, the first one included
+    pub fn goals(mut self, goals: usize) -> Self {
+        self.goals = goals;
+        self
+    }
+
+    pub fn goal_mode(mut self, goal_mode: GoalMode) -> Self {
+        self.goal_mode = goal_mode;
+        self
+    }
+
+    // Items for hand-made layouts
+    pub fn placed_items(mut self, placed_items: Vec<(Position, Item)>) -> Self {
+        self.placed_items = placed_items;
+        self
+    }
+
+    // Coins and gems scattered over generated mazes
+    pub fn items(mut self, items: usize) -> Self {
+        self.items = items;
+        self
+    }
+
+    pub fn end_condition(mut self, end_condition: EndCondition) -> Self {
+        self.end_condition = end_condition;
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn view_range(mut self, view_range: usize) -> Self {
+        self.view_range = view_range;
+        self
+    }
+
+    // None lets the episode run until the goal is reached
+    pub fn max_steps(mut self, max_steps: Option<usize>) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    // Key and door pairs added to generated mazes, hand-made ones keep their own
+    pub fn doors(mut self, doors: usize) -> Self {
+        self.doors = doors;
+        self
+    }
+
+    // Teleporter pairs added to generated mazes
+    pub fn teleporters(mut self, teleporters: usize) -> Self {
+        self.teleporters = teleporters;
+        self
+    }
+
+    // Mud, water and road on generated mazes, everything else stays plain
+    pub fn terrain(mut self, terrain: bool) -> Self {
+        self.terrain = terrain;
+        self
+    }
+
+    // Ice patches and conveyor belts added to generated mazes
+    pub fn slippery(mut self, slippery: usize) -> Self {
+        self.slippery = slippery;
+        self
+    }
+
+    // Pits and spikes added to generated mazes
+    pub fn hazards(mut self, hazards: usize) -> Self {
+        self.hazards = hazards;
+        self
+    }
+
+    // Hidden hazards look walkable in observations until the player finds out
+    pub fn hazards_visible(mut self, hazards_visible: bool) -> Self {
+        self.hazards_visible = hazards_visible;
+        self
+    }
+
+    pub fn health(mut self, health: u32) -> Self {
+        self.health = health;
+        self
+    }
+
+    // Enemies added to generated mazes
+    pub fn enemies(mut self, enemies: usize) -> Self {
+        self.enemies = enemies;
+        self
+    }
+
+    // Walls of generated mazes that open and close while the episode runs
+    pub fn dynamic_walls(mut self, dynamic_walls: usize) -> Self {
+        self.dynamic_walls = dynamic_walls;
+        self
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let (width, height) = (self.width, self.height);
+
+        if width == 0 || height == 0 {
+            return Err(ConfigError::EmptyDimensions { width, height });
+        }
+        if width > self.max_width || height > self.max_height {
+            return Err(ConfigError::TooLarge {
+                width,
+                height,
+                max_width: self.max_width,
+                max_height: self.max_height,
+            });
+        }
+        if width * height < 2 + self.extra_goals.len() + self.placed_items.len() {
+            return Err(ConfigError::TooSmall { width, height });
+        }
+
+        if let Generator::Random { wall_density } = self.generator
+            && !(0.0..=1.0).contains(&wall_density)
+        {
+            return Err(ConfigError::InvalidWallDensity(wall_density));
+        }
+
+        if self.view_range == 0 {
+            return Err(ConfigError::InvalidViewRange(self.view_range));
+        }
+        if self.max_steps == Some(0) {
+            return Err(ConfigError::InvalidStepLimit(0));
+        }
+        if self.doors > KeyColor::ALL.len() {
+            return Err(ConfigError::TooManyDoors(self.doors));
+        }
+        if !(1..=MAX_HEALTH).contains(&self.health) {
+            return Err(ConfigError::InvalidHealth(self.health));
+        }
+        if self.teleporters > MAX_TELEPORTERS {
+            return Err(ConfigError::TooManyTeleporters(self.teleporters));
+        }
+        if !(1..=MAX_GOALS).contains(&self.goals) {
+            return Err(ConfigError::InvalidGoalCount(self.goals));
+        }
+        if self.extra_goals.len() >= MAX_GOALS {
+            return Err(ConfigError::InvalidGoalCount(self.extra_goals.len() + 1));
+        }
+        for items in [self.items, self.placed_items.len()] {
+            if items > MAX_ITEMS {
+                return Err(ConfigError::TooManyItems(items));
+            }
+        }
+
+        let extra_goals = self.extra_goals.iter().map(|&goal| ("goal", goal));
+        let items = self.placed_items.iter().map(|&(item, _)| ("item", item));
+        for (name, position) in [("start", self.start), ("goal", self.goal)]
+            .into_iter()
+            .filter_map(|(name, position)| Some((name, position?)))
+            .chain(extra_goals)
+            .chain(items)
+        {
+            if position.x >= width || position.y >= height {
+                return Err(ConfigError::OutOfBounds { name, position });
+            }
+            // Generated mazes get the tile carved out, hand-made ones must be right already
+            if let Some(tiles) = &self.tiles
+                && tiles[position].tile_type == TileType::Wall
+            {
+                return Err(ConfigError::OnWall { name, position });
+            }
+        }
+
+        let goals: Vec<Position> = self
+            .goal
+            .into_iter()
+            .chain(self.extra_goals.iter().copied())
+            .collect();
+        if let Some(start) = self.start
+            && goals.contains(&start)
+        {
+            return Err(ConfigError::StartIsGoal(start));
+        }
+        for (index, goal) in goals.iter().enumerate() {
+            if goals[..index].contains(goal) {
+                return Err(ConfigError::DuplicateGoal(*goal));
+            }
+        }
+        for (index, &(position, _)) in self.placed_items.iter().enumerate() {
+            if Some(position) == self.start
+                || goals.contains(&position)
+                || self.placed_items[..index]
+                    .iter()
+                    .any(|&(other, _)| other == position)
+                || self
+                    .tiles
+                    .as_ref()
+                    .is_some_and(|tiles| tiles[position].tile_type != TileType::Walkable)
+            {
+                return Err(ConfigError::ItemMisplaced(position));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn build(self) -> Result<GameState, ConfigError> {
+        self.validate()?;
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let _span = debug_span!("build", seed, width = self.width, height = self.height).entered();
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let generated = self.tiles.is_none();
+        // Whether anything reachable is left to collect depends on the layout,
+        // so a generated maze keeps drawing new ones from the same seed
+        let mut attempts = 0;
+        let Layout {
+            tiles,
+            start: position,
+            goal,
+            extra_goals,
+            items,
+            doors,
+        } = loop {
+            attempts += 1;
+            let layout = self.layout(&mut rng)?;
+            if self.end_condition != EndCondition::CollectAll || !layout.items.is_empty() {
+                break layout;
+            }
+            if !generated || attempts == MAX_LAYOUT_ATTEMPTS {
+                return Err(ConfigError::NothingToCollect);
+            }
+            debug!(attempts, "no items placed, generating again");
+        };
+        let teleporters = teleporter_pairs(&tiles)?;
+
+        let mut visits = Grid::new(self.width, self.height, 0);
+        visits[position] = 1;
+
+        info!(
+            seed,
+            width = self.width,
+            height = self.height,
+            generator = self.generator.name(),
+            "episode started"
+        );
+
+        let player = Player {
+            direction: self.direction,
+            position,
+            inventory: Inventory::default(),
+            health: self.health,
+            reached: 0,
+            collected: 0,
+        };
+
+        let mut game_state = GameState {
+            width: self.width,
+            height: self.height,
+            player,
+            goal,
+            extra_goals,
+            goal_mode: self.goal_mode,
+            items,
+            end_condition: self.end_condition,
+            outcome: Outcome::InProgress,
+            start: player,
+            tiles,
+            generator: self.generator,
+            seed,
+            view_range: self.view_range,
+            steps: 0,
+            turns: 0,
+            cost: 0,
+            doors,
+            teleporters,
+            terrain: self.terrain,
+            slippery: 0,
+            hazards: 0,
+            hazards_visible: self.hazards_visible,
+            enemies: Vec::new(),
+            start_enemies: Vec::new(),
+            dynamic_walls: Vec::new(),
+            revision: 0,
+            max_steps: self.max_steps,
+            visits,
+        };
+        // Placement checks solvability, which needs the finished game
+        if generated {
+            game_state.slippery = place_slippery(&mut game_state, self.slippery, &mut rng);
+            game_state.hazards = place_hazards(&mut game_state, self.hazards, &mut rng);
+            place_enemies(&mut game_state, self.enemies, &mut rng);
+            place_dynamic_walls(&mut game_state, self.dynamic_walls, &mut rng);
+        }
+
+        Ok(game_state)
+    }
+
+    // Tiles, start, goals and items, generated where the builder left them open
+    fn layout(&self, rng: &mut StdRng) -> Result<Layout, ConfigError> {
+        let generated = self.tiles.is_none();
+        let mut tiles = match &self.tiles {
+            Some(tiles) => tiles.clone(),
+            None => self.generator.generate(self.width, self.height, rng),
+        };
+
+        let mut extra_goals = self.extra_goals.clone();
+        let mut items = self.placed_items.clone();
+        // Everything the start must stay off
+        let taken: Vec<Position> = self
+            .goal
+            .into_iter()
+            .chain(extra_goals.iter().copied())
+            .chain(items.iter().map(|&(position, _)| position))
+            .collect();
+        let position = match self.start {
+            Some(start) => start,
+            None => random_walkable(&tiles, &taken, rng).ok_or_else(|| self.too_small())?,
+        };
+
+        // Generate goal position different from player and off every item
+        let goal = match self.goal {
+            Some(goal) => goal,
+            None if !extra_goals.is_empty() => extra_goals.remove(0),
+            None => {
+                let taken: Vec<Position> = taken.into_iter().chain([position]).collect();
+                random_walkable(&tiles, &taken, rng).ok_or_else(|| self.too_small())?
+            }
+        };
+
+        // Make sure the player doesn't start inside a wall
+        tiles[position].tile_type = TileType::Walkable;
+
+        // Mark goal tiles
+        for &goal in std::iter::once(&goal).chain(&extra_goals) {
+            tiles[goal].tile_type = TileType::Goal;
+        }
+
+        let doors = if generated {
+            place_doors(&mut tiles, position, goal, self.doors, rng)
+        } else {
+            0
+        };
+        if generated {
+            place_teleporters(&mut tiles, position, self.teleporters, rng);
+            if self.terrain {
+                place_terrain(&mut tiles, position, rng);
+            }
+            let missing = (self.goals - 1).saturating_sub(extra_goals.len());
+            extra_goals.extend(place_goals(&mut tiles, position, missing, rng));
+            let missing = self.items.saturating_sub(items.len());
+            items.extend(place_items(&tiles, position, &items, missing, rng));
+        }
+
+        Ok(Layout {
+            tiles,
+            start: position,
+            goal,
+            extra_goals,
+            items,
+            doors,
+        })
+    }
+
+    fn too_small(&self) -> ConfigError {
+        ConfigError::TooSmall {
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+struct Layout {
+    tiles: Grid<Tile>,
+    start: Position,
+    goal: Position,
+    extra_goals: Vec<Position>,
+    items: Vec<(Position, Item)>,
+    doors: usize,
+}
+
+// Pairs are indexed by their id, which has to be dense
+fn teleporter_pairs(tiles: &Grid<Tile>) -> Result<Vec<[Position; 2]>, ConfigError> {
+    let mut ends: Vec<Vec<Position>> = Vec::new();
+    for (position, tile) in tiles.iter() {
+        if let TileType::Teleporter(id) = tile.tile_type {
+            let id = id as usize;
+            if ends.len() <= id {
+                ends.resize(id + 1, Vec::new());
+            }
+            ends[id].push(position);
+        }
+    }
+
+    ends.into_iter()
+        .enumerate()
+        .map(|(id, ends)| match ends[..] {
+            [first, second] => Ok([first, second]),
+            _ => Err(ConfigError::UnpairedTeleporter(id as u8)),
+        })
+        .collect()
+}
+
+fn random_walkable(tiles: &Grid<Tile>, exclude: &[Position], rng: &mut StdRng) -> Option<Position> {
+    // Keys, doors, pits and teleporters would be overwritten by the start or
+    // goal, so only plain ground qualifies. Carving a wall is the next best,
+    // and any other tile is better than none.
+    for tile_type in [Some(TileType::Walkable), Some(TileType::Wall), None] {
+        let candidates: Vec<Position> = tiles
+            .iter()
+            .filter(|&(position, tile)| {
+                tile_type.is_none_or(|tile_type| tile.tile_type == tile_type)
+                    && !exclude.contains(&position)
+            })
+            .map(|(position, _)| position)
+            .collect();
+
+        if let Some(&position) = candidates.choose(rng) {
+            return Some(position);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map_file::parse_map;
+
+    const ORIGIN: Position = Position { x: 0, y: 0 };
+
+    fn error(builder: GameStateBuilder) -> Option<ConfigError> {
+        builder.seed(0).build().err()
+    }
+
+    fn small() -> GameStateBuilder {
+        GameState::builder().size(5, 5)
+    }
+
+    #[test]
+    fn defaults_build() {
+        assert_eq!(error(GameState::builder()), None);
+    }
+
+    #[test]
+    fn rejects_bad_dimensions() {
+        assert_eq!(
+            error(GameState::builder().size(0, 5)),
+            Some(ConfigError::EmptyDimensions {
+                width: 0,
+                height: 5
+            })
+        );
+        assert_eq!(
+            error(GameState::builder().size(20, 10).max_size(16, 16)),
+            Some(ConfigError::TooLarge {
+                width: 20,
+                height: 10,
+                max_width: 16,
+                max_height: 16
+            })
+        );
+        assert_eq!(
+            error(GameState::builder().size(1, 1)),
+            Some(ConfigError::TooSmall {
+                width: 1,
+                height: 1
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_a_map_with_every_tile_taken() {
+        // The goal and the coin leave nowhere for the start
+        let builder = parse_map("G$\n").unwrap();
+        assert_eq!(
+            error(builder),
+            Some(ConfigError::TooSmall {
+                width: 2,
+                height: 1
+            })
+        );
+        let builder = parse_map("①②\n").unwrap();
+        assert_eq!(
+            error(builder),
+            Some(ConfigError::TooSmall {
+                width: 2,
+                height: 1
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_settings() {
+        let cases = [
+            (
+                small().wall_density(1.5),
+                ConfigError::InvalidWallDensity(1.5),
+            ),
+            (small().view_range(0), ConfigError::InvalidViewRange(0)),
+            (small().max_steps(Some(0)), ConfigError::InvalidStepLimit(0)),
+            (
+                small().doors(KeyColor::ALL.len() + 1),
+                ConfigError::TooManyDoors(KeyColor::ALL.len() + 1),
+            ),
+            (small().health(0), ConfigError::InvalidHealth(0)),
+            (
+                small().health(MAX_HEALTH + 1),
+                ConfigError::InvalidHealth(MAX_HEALTH + 1),
+            ),
+            (
+                small().teleporters(MAX_TELEPORTERS + 1),
+                ConfigError::TooManyTeleporters(MAX_TELEPORTERS + 1),
+            ),
+            (small().goals(0), ConfigError::InvalidGoalCount(0)),
+            (
+                small().goals(MAX_GOALS + 1),
+                ConfigError::InvalidGoalCount(MAX_GOALS + 1),
+            ),
+            (
+                small().items(MAX_ITEMS + 1),
+                ConfigError::TooManyItems(MAX_ITEMS + 1),
+            ),
+        ];
+
+        for (builder, expected) in cases {
+            assert_eq!(error(builder), Some(expected));
+        }
+    }
+
+    #[test]
+    fn rejects_misplaced_positions() {
+        let outside = Position { x: 5, y: 0 };
+        let next = Position { x: 1, y: 0 };
+        let cases = [
+            (
+                small().start(outside),
+                ConfigError::OutOfBounds {
+                    name: "start",
+                    position: outside,
+                },
+            ),
+            (
+                small().goal(outside),
+                ConfigError::OutOfBounds {
+                    name: "goal",
+                    position: outside,
+                },
+            ),
+            (
+                parse_map("#.G\n").unwrap().start(ORIGIN),
+                ConfigError::OnWall {
+                    name: "start",
+                    position: ORIGIN,
+                },
+            ),
+            (
+                small().start(ORIGIN).goal(ORIGIN),
+                ConfigError::StartIsGoal(ORIGIN),
+            ),
+            (
+                small().goal(next).extra_goals(vec![next]),
+                ConfigError::DuplicateGoal(next),
+            ),
+            (
+                small()
+                    .start(ORIGIN)
+                    .placed_items(vec![(ORIGIN, Item::Coin)]),
+                ConfigError::ItemMisplaced(ORIGIN),
+            ),
+            (
+                small().placed_items(vec![(next, Item::Coin), (next, Item::Gem)]),
+                ConfigError::ItemMisplaced(next),
+            ),
+        ];
+
+        for (builder, expected) in cases {
+            assert_eq!(error(builder), Some(expected));
+        }
+    }
+
+    #[test]
+    fn rejects_an_unpaired_teleporter() {
+        let builder = parse_map("^.0G\n").unwrap();
+        assert_eq!(error(builder), Some(ConfigError::UnpairedTeleporter(0)));
+    }
+
+    #[test]
+    fn rejects_collecting_from_a_map_without_items() {
+        let builder = parse_map("^.G\n")
+            .unwrap()
+            .end_condition(EndCondition::CollectAll);
+        assert_eq!(error(builder), Some(ConfigError::NothingToCollect));
+    }
+
+    #[test]
+    fn keeps_generating_until_something_can_be_collected() {
+        // Dense walls often shut the start in, every seed still gets an item
+        for seed in 0..50 {
+            let game_state = GameState::builder()
+                .size(20, 20)
+                .wall_density(0.7)
+                .items(1)
+                .end_condition(EndCondition::CollectAll)
+                .seed(seed)
+                .build()
+                .unwrap();
+            assert_eq!(game_state.items.len(), 1);
+        }
+    }
+
+    #[test]
+    fn keeps_a_random_goal_off_placed_items() {
+        for seed in 0..50 {
+            let game_state = GameState::builder()
+                .size(3, 1)
+                .wall_density(0.0)
+                .start(ORIGIN)
+                .placed_items(vec![(Position { x: 1, y: 0 }, Item::Coin)])
+                .seed(seed)
+                .build()
+                .unwrap();
+            assert_eq!(game_state.goal, Position { x: 2, y: 0 });
+        }
+    }
+}
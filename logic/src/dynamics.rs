@@ -0,0 +1,35 @@
+use crate::types::Position;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WallSchedule {
+    // Closed for `period` steps, then open for as many, starting `offset` steps in
+    Periodic { period: usize, offset: usize },
+    // Flips with the given probability every step
+    Random { probability: f64 },
+}
+
+// A tile that switches between wall and walkable as the episode goes on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DynamicWall {
+    pub position: Position,
+    pub schedule: WallSchedule,
+    pub closed_at_start: bool,
+}
+
+impl DynamicWall {
+    // Whether the wall wants to be closed after `step` steps, given whether it
+    // is closed now and a random roll for random schedules
+    pub fn closed_after(&self, step: usize, closed: bool, roll: u64) -> bool {
+        match self.schedule {
+            WallSchedule::Periodic { period, offset } => {
+                ((step + offset) / period.max(1)).is_multiple_of(2)
+            }
+            WallSchedule::Random { probability } => {
+                // The top 53 bits as a uniform number in [0, 1)
+                let uniform = (roll >> 11) as f64 / (1u64 << 53) as f64;
+                let flip = uniform < probability;
+                closed != flip
+            }
+        }
+    }
+}
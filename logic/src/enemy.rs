@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use crate::grid::Grid;
+use crate::types::{Direction, Inventory, Position, Tile, TileType};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EnemyBehavior {
+    // Walks the route back and forth, `next` is the index it heads to
+    Patrol {
+        route: Vec<Position>,
+        next: usize,
+        forward: bool,
+    },
+    RandomWalk,
+    // Takes a step along the shortest path to the player
+    Chaser,
+}
+
+impl EnemyBehavior {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EnemyBehavior::Patrol { .. } => "patrol",
+            EnemyBehavior::RandomWalk => "random",
+            EnemyBehavior::Chaser => "chaser",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Enemy {
+    pub position: Position,
+    pub behavior: EnemyBehavior,
+}
+
+impl Enemy {
+    pub fn patrol(route: Vec<Position>) -> Self {
+        Enemy {
+            position: route[0],
+            behavior: EnemyBehavior::Patrol {
+                route,
+                next: 1,
+                forward: true,
+            },
+        }
+    }
+
+    // Enemies have no keys and keep out of pits, everything else they walk
+    // over like plain ground
+    pub fn can_enter(tiles: &Grid<Tile>, position: Position) -> bool {
+        let tile_type = tiles[position].tile_type;
+        !tile_type.blocks(Inventory::default()) && tile_type != TileType::Pit
+    }
+
+    // Moves one tile, or stays put when boxed in. `roll` is the randomness for
+    // random walkers, so the game decides where it comes from.
+    pub fn advance(&mut self, tiles: &Grid<Tile>, player: Position, roll: u64) {
+        let next = match &mut self.behavior {
+            EnemyBehavior::Patrol {
+                route,
+                next,
+                forward,
+            } => {
+                if route.len() < 2 {
+                    return;
+                }
+                let target = route[*next];
+                if *next == route.len() - 1 || (*next == 0 && !*forward) {
+                    *forward = !*forward;
+                }
+                *next = if *forward { *next + 1 } else { *next - 1 };
+                Some(target)
+            }
+            EnemyBehavior::RandomWalk => {
+                let open: Vec<Position> = tiles
+                    .neighbours(self.position)
+                    .map(|(_, neighbour)| neighbour)
+                    .filter(|&neighbour| Self::can_enter(tiles, neighbour))
+                    .collect();
+                (!open.is_empty()).then(|| open[(roll % open.len() as u64) as usize])
+            }
+            EnemyBehavior::Chaser => first_step(tiles, self.position, player),
+        };
+
+        if let Some(next) = next
+            && Self::can_enter(tiles, next)
+        {
+            self.position = next;
+        }
+    }
+}
+
+// First tile on a shortest path from `from` to `to`, by breadth-first search
+fn first_step(tiles: &Grid<Tile>, from: Position, to: Position) -> Option<Position> {
+    if from == to {
+        return None;
+    }
+
+    let mut first: Grid<Option<Position>> = Grid::new(tiles.width(), tiles.height(), None);
+    let mut queue = VecDeque::new();
+    for direction in Direction::ALL {
+        if let Some(next) = tiles.neighbour(from, direction)
+            && Enemy::can_enter(tiles, next)
+        {
+            first[next] = Some(next);
+            queue.push_back(next);
+        }
+    }
+
+    while let Some(position) = queue.pop_front() {
+        if position == to {
+            return first[position];
+        }
+        for (_, next) in tiles.neighbours(position) {
+            if next != from && first[next].is_none() && Enemy::can_enter(tiles, next) {
+                first[next] = first[position];
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
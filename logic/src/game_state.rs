@@ -1,74 +1,125 @@
-use rand::prelude::*;
-use crate::types::{Tile, Position, Direction, Player, Action, Observation, TileType};
+use tracing::{debug, info};
 
-#[derive(Debug)]
+use crate::builder::{ConfigError, GameStateBuilder};
+use crate::dynamics::DynamicWall;
+use crate::enemy::Enemy;
+use crate::generator::Generator;
+use crate::grid::Grid;
+use crate::types::{
+    Action, Direction, EndCondition, GoalMode, Inventory, Item, Observation, Outcome, Player,
+    Position, Terrain, Tile, TileType,
+};
+
+// What an action that doesn't enter a tile costs, turning or bumping into a wall
+pub const TURN_COST: usize = 1;
+pub const SPIKE_DAMAGE: u32 = 1;
+// Keeps wall rolls apart from enemy rolls for the same step
+const WALL_ROLL_SALT: u64 = 0x5bd1_e995;
+
+// Everything a single action can change, so it can be taken back
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    player: Player,
+    enemies: Vec<Enemy>,
+    // Whether each dynamic wall was closed
+    walls: Vec<bool>,
+    outcome: Outcome,
+    steps: usize,
+    turns: usize,
+    cost: usize,
+}
+
+// The outcome of one action, see `GameState::transition`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transition {
+    pub player: Player,
+    pub cost: usize,
+    // A move that ran into something that blocks
+    pub blocked: bool,
+}
+
+#[derive(Debug, Clone)]
 pub struct GameState {
     pub width: usize,
     pub height: usize,
-    pub tiles: Vec<Vec<Tile>>,
+    pub tiles: Grid<Tile>,
     pub player: Player,
     pub goal: Position,
-    pub game_over: bool,
+    // Goals after the first, in checkpoint order
+    pub extra_goals: Vec<Position>,
+    pub goal_mode: GoalMode,
+    // Where each item lies, the player's `collected` bits say which are gone
+    pub items: Vec<(Position, Item)>,
+    pub end_condition: EndCondition,
+    pub outcome: Outcome,
+    // Where and how the episode started, for restarting it
+    pub start: Player,
+    pub generator: Generator,
+    pub seed: u64,
+    // How many tiles ahead the player can see
+    pub view_range: usize,
+    // Actions taken so far, turns included
+    pub steps: usize,
+    // The subset of steps that only changed direction
+    pub turns: usize,
+    // Terrain cost of every move plus TURN_COST for everything else
+    pub cost: usize,
+    // Key and door pairs the generator placed
+    pub doors: usize,
+    // Both ends of each teleporter, indexed by the id on their tiles
+    pub teleporters: Vec<[Position; 2]>,
+    // Whether the generator laid out terrain
+    pub terrain: bool,
+    // Ice patches and conveyor belts the generator placed
+    pub slippery: usize,
+    // Pits and spikes the generator placed
+    pub hazards: usize,
+    // Whether observations show hazards or pass them off as walkable tiles
+    pub hazards_visible: bool,
+    pub enemies: Vec<Enemy>,
+    // Where the enemies were when the episode started, for restarting it
+    pub start_enemies: Vec<Enemy>,
+    pub dynamic_walls: Vec<DynamicWall>,
+    // Bumped whenever a tile changes, so front-ends know to redraw the map
+    pub revision: u64,
+    pub max_steps: Option<usize>,
+    // Times the player has come to rest on each tile this episode, the start
+    // counts once
+    pub visits: Grid<u32>,
 }
 
 impl GameState {
-    pub fn new(width: usize, height: usize) -> Self {
-        let mut rng = rand::rng();
-
-        let mut tiles = Self::create_maze(width, height);
-
-        let direction = Direction::Up;
-
-        let position = Position {
-            x: rng.random_range(..width),
-            y: rng.random_range(..height),
-        };
-
-        let player = Player {
-            direction,
-            position,
-        };
-
-        // Generate goal position different from player
-        let mut goal = Position {
-            x: rng.random_range(..width),
-            y: rng.random_range(..height),
-        };
-        while goal.x == position.x && goal.y == position.y {
-            goal = Position {
-                x: rng.random_range(..width),
-                y: rng.random_range(..height),
-            };
-        }
+    pub fn builder() -> GameStateBuilder {
+        GameStateBuilder::default()
+    }
 
-        // Mark goal tile
-        tiles[goal.x][goal.y].tile_type = TileType::Goal;
+    // Panics on invalid dimensions, use `builder` to handle them gracefully
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::builder()
+            .size(width, height)
+            .build()
+            .unwrap_or_else(|error| panic!("invalid maze: {error}"))
+    }
 
-        GameState {
-            width,
-            height,
-            player,
-            goal,
-            game_over: false,
-            tiles,
-        }
+    pub fn with_generator(width: usize, height: usize, generator: Generator, seed: u64) -> Self {
+        Self::builder()
+            .size(width, height)
+            .generator(generator)
+            .seed(seed)
+            .build()
+            .unwrap_or_else(|error| panic!("invalid maze: {error}"))
     }
 
     pub fn get_player_view(&self) -> Vec<Position> {
-        // returns up to 3 positions in front of the player based on direction
-        // stops at walls or non-walkable tiles
+        // returns up to view_range positions in front of the player based on direction
+        // stops at walls, and just after doors
 
         let mut view_positions = Vec::new();
-        let (dx, dy) = match self.player.direction {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
+        let (dx, dy) = self.player.direction.delta();
         let mut x = self.player.position.x as isize;
         let mut y = self.player.position.y as isize;
 
-        for _ in 0..3 {
+        for _ in 0..self.view_range {
             x += dx;
             y += dy;
 
@@ -76,7 +127,10 @@ impl GameState {
                 break;
             }
 
-            let tile = &self.tiles[x as usize][y as usize];
+            let tile = &self.tiles[Position {
+                x: x as usize,
+                y: y as usize,
+            }];
             if tile.tile_type == TileType::Wall {
                 break;
             }
@@ -85,133 +139,574 @@ impl GameState {
                 x: x as usize,
                 y: y as usize,
             });
+
+            if tile.tile_type.is_opaque() {
+                break;
+            }
         }
 
         view_positions
     }
 
-    pub fn create_maze(width: usize, height: usize) -> Vec<Vec<Tile>> {
-        let mut rng = rand::rng();
-        let tiles: Vec<Vec<Tile>> = (0..width)
-            .map(|_| {
-                (0..height)
-                    .map(|_| Tile {
-                        tile_type: if rng.random_bool(0.8) {
-                            TileType::Walkable
-                        } else {
-                            TileType::Wall
-                        },
-                    })
-                    .collect()
+    // Where the player ends up after stepping onto the tile
+    pub fn landing(&self, position: Position) -> Position {
+        match self.tiles[position].tile_type {
+            TileType::Teleporter(id) => {
+                let [first, second] = self.teleporters[id as usize];
+                if position == first { second } else { first }
+            }
+            _ => position,
+        }
+    }
+
+    // Steps onto the neighbouring tile unless it blocks, following teleporters
+    // and picking up keys. Returns the tile the player ends up on.
+    fn enter(&self, player: &mut Player, direction: Direction) -> Option<Position> {
+        let next = self
+            .tiles
+            .neighbour(player.position, direction)
+            .filter(|&next| !self.tiles[next].tile_type.blocks(player.inventory))?;
+        if let TileType::Key(color) = self.tiles[next].tile_type {
+            player.inventory.insert(color);
+        }
+        player.position = self.landing(next);
+        if let Some(index) = self.item_at(player.position) {
+            player.collected |= 1 << index;
+        }
+        if self.tiles[player.position].tile_type == TileType::Spikes {
+            player.health = player.health.saturating_sub(SPIKE_DAMAGE);
+        }
+        Some(player.position)
+    }
+
+    // What an action does to the player, without touching the game. After the
+    // player's own move they slide across ice in the direction they were going,
+    // and a conveyor under them pushes them once per step. A goal ends the
+    // chain, and so does dying or coming back to a tile heading the same way.
+    pub fn transition(&self, player: Player, action: Action) -> Transition {
+        let mut player = player;
+        let direction = Direction::from(action);
+        let mut blocked = false;
+        let mut momentum = None;
+
+        let cost = if player.direction != direction {
+            player.direction = direction;
+            TURN_COST
+        } else if let Some(landing) = self.enter(&mut player, direction) {
+            momentum = Some(direction);
+            self.tiles[landing].terrain.cost()
+        } else {
+            blocked = true;
+            TURN_COST
+        };
+
+        let mut pushed = false;
+        let mut seen = Vec::new();
+        while !self.is_goal(player.position) && !self.is_dead(&player) {
+            let direction = match (self.tiles[player.position].tile_type, momentum) {
+                (TileType::Ice, Some(direction)) => direction,
+                (TileType::Conveyor(direction), _) if !pushed => {
+                    pushed = true;
+                    direction
+                }
+                _ => break,
+            };
+            if seen.contains(&(player.position, direction)) {
+                debug!(position = ?player.position, "forced movement loops, stopping");
+                break;
+            }
+            seen.push((player.position, direction));
+
+            if self.enter(&mut player, direction).is_none() {
+                break;
+            }
+            momentum = Some(direction);
+        }
+        self.reach(&mut player);
+
+        Transition {
+            player,
+            cost,
+            blocked,
+        }
+    }
+
+    pub fn goals(&self) -> impl Iterator<Item = Position> + '_ {
+        std::iter::once(self.goal).chain(self.extra_goals.iter().copied())
+    }
+
+    pub fn goal_count(&self) -> usize {
+        1 + self.extra_goals.len()
+    }
+
+    pub fn is_goal(&self, position: Position) -> bool {
+        self.tiles[position].tile_type == TileType::Goal
+    }
+
+    // Marks the goal under the player as reached, unless it is a checkpoint
+    // that comes later in the order
+    fn reach(&self, player: &mut Player) {
+        let Some(index) = self.goals().position(|goal| goal == player.position) else {
+            return;
+        };
+        if self.goal_mode != GoalMode::Ordered || index == player.reached.count_ones() as usize {
+            player.reached |= 1 << index;
+        }
+    }
+
+    pub fn goals_needed(&self) -> usize {
+        match self.goal_mode {
+            GoalMode::Any => 1,
+            GoalMode::All | GoalMode::Ordered => self.goal_count(),
+        }
+    }
+
+    pub fn goals_done(&self, player: &Player) -> bool {
+        player.reached.count_ones() as usize >= self.goals_needed()
+    }
+
+    // Index into `items` of the item lying there at the start of the episode
+    pub fn item_at(&self, position: Position) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|&(item_position, _)| item_position == position)
+    }
+
+    pub fn items_left(&self, player: &Player) -> usize {
+        self.items.len() - player.collected.count_ones() as usize
+    }
+
+    // Items still lying around, with their index into `items`
+    pub fn remaining_items(
+        &self,
+        player: &Player,
+    ) -> impl Iterator<Item = (usize, Position, Item)> + '_ {
+        let collected = player.collected;
+        self.items
+            .iter()
+            .enumerate()
+            .filter(move |&(index, _)| collected & (1 << index) == 0)
+            .map(|(index, &(position, item))| (index, position, item))
+    }
+
+    pub fn score(&self) -> u32 {
+        self.items
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| self.player.collected & (1 << index) != 0)
+            .map(|(_, (_, item))| item.value())
+            .sum()
+    }
+
+    pub fn is_won(&self, player: &Player) -> bool {
+        match self.end_condition {
+            EndCondition::Goal => self.goals_done(player),
+            EndCondition::CollectAll => self.items_left(player) == 0,
+        }
+    }
+
+    // Where the player has to go next to get closer to winning, any one of
+    // them will do
+    pub fn targets(&self, player: &Player) -> Vec<Position> {
+        match self.end_condition {
+            EndCondition::Goal => self.pending_goals(player),
+            EndCondition::CollectAll => self
+                .remaining_items(player)
+                .map(|(_, position, _)| position)
+                .collect(),
+        }
+    }
+
+    // Goals that would count if the player reached them next
+    pub fn pending_goals(&self, player: &Player) -> Vec<Position> {
+        let unreached = self
+            .goals()
+            .enumerate()
+            .filter(|&(index, _)| player.reached & (1 << index) == 0)
+            .map(|(_, goal)| goal);
+        match self.goal_mode {
+            GoalMode::Any => self.goals().collect(),
+            GoalMode::All => unreached.collect(),
+            GoalMode::Ordered => unreached.take(1).collect(),
+        }
+    }
+
+    // Every enemy takes a step after the player, returns whether one of them
+    // ends up on or walks through the player
+    fn move_enemies(&mut self) -> bool {
+        let player = self.player.position;
+        let mut caught = false;
+        for (index, enemy) in self.enemies.iter_mut().enumerate() {
+            let from = enemy.position;
+            enemy.advance(&self.tiles, player, step_roll(self.seed, self.steps, index));
+            caught |= from == player || enemy.position == player;
+        }
+        caught
+    }
+
+    pub fn is_dynamic_wall(&self, position: Position) -> bool {
+        self.dynamic_walls
+            .iter()
+            .any(|wall| wall.position == position)
+    }
+
+    fn walls_closed(&self) -> Vec<bool> {
+        self.dynamic_walls
+            .iter()
+            .map(|wall| self.tiles[wall.position].tile_type == TileType::Wall)
+            .collect()
+    }
+
+    fn set_walls(&mut self, closed: &[bool]) {
+        let mut changed = false;
+        for (wall, &closed) in self.dynamic_walls.iter().zip(closed) {
+            let tile_type = if closed {
+                TileType::Wall
+            } else {
+                TileType::Walkable
+            };
+            let tile = &mut self.tiles[wall.position];
+            changed |= tile.tile_type != tile_type;
+            tile.tile_type = tile_type;
+        }
+        if changed {
+            self.revision += 1;
+        }
+    }
+
+    // Walls only close on tiles nobody stands on, otherwise they wait a step
+    fn update_walls(&mut self) {
+        let closed: Vec<bool> = self
+            .dynamic_walls
+            .iter()
+            .enumerate()
+            .map(|(index, wall)| {
+                let closed = self.tiles[wall.position].tile_type == TileType::Wall;
+                let roll = step_roll(self.seed ^ WALL_ROLL_SALT, self.steps, index);
+                let occupied = wall.position == self.player.position
+                    || self
+                        .enemies
+                        .iter()
+                        .any(|enemy| enemy.position == wall.position);
+                wall.closed_after(self.steps, closed, roll) && !occupied
             })
             .collect();
+        self.set_walls(&closed);
+    }
+
+    pub fn is_dead(&self, player: &Player) -> bool {
+        player.health == 0 || self.tiles[player.position].tile_type == TileType::Pit
+    }
+
+    pub fn create_maze(width: usize, height: usize) -> Grid<Tile> {
+        Generator::default().generate(width, height, &mut rand::rng())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            player: self.player,
+            enemies: self.enemies.clone(),
+            walls: self.walls_closed(),
+            outcome: self.outcome,
+            steps: self.steps,
+            turns: self.turns,
+            cost: self.cost,
+        }
+    }
+
+    // Only meant for snapshots taken earlier in the same episode
+    pub fn undo(&mut self, snapshot: Snapshot) {
+        if snapshot.player.position != self.player.position {
+            let visits = &mut self.visits[self.player.position];
+            *visits = visits.saturating_sub(1);
+        }
 
-        tiles
+        self.player = snapshot.player;
+        self.enemies = snapshot.enemies;
+        self.set_walls(&snapshot.walls);
+        self.outcome = snapshot.outcome;
+        self.steps = snapshot.steps;
+        self.turns = snapshot.turns;
+        self.cost = snapshot.cost;
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.outcome != Outcome::InProgress
     }
 
     pub fn apply_action(&mut self, action: Action) {
-        if self.game_over {
+        if self.is_over() {
             return;
         }
 
-        match action {
-            Action::Up => {
-                if self.player.direction == Direction::Up {
-                    if self.player.position.y > 0 {
-                        let tile = &self.tiles[self.player.position.x][self.player.position.y - 1];
-                        if tile.tile_type != TileType::Wall {
-                            self.player.position.y -= 1;
-                            self.check_goal();
-                        }
-                    }
-                } else {
-                    self.player.direction = Direction::Up
-                }
-            }
-            Action::Down => {
-                if self.player.direction == Direction::Down {
-                    if self.player.position.y < self.height - 1 {
-                        let tile = &self.tiles[self.player.position.x][self.player.position.y + 1];
-                        if tile.tile_type != TileType::Wall {
-                            self.player.position.y += 1;
-                            self.check_goal();
-                        }
-                    }
-                } else {
-                    self.player.direction = Direction::Down
-                }
-            }
-            Action::Right => {
-                if self.player.direction == Direction::Right {
-                    if self.player.position.x < self.width - 1 {
-                        let tile = &self.tiles[self.player.position.x + 1][self.player.position.y];
-                        if tile.tile_type != TileType::Wall {
-                            self.player.position.x += 1;
-                            self.check_goal();
-                        }
-                    }
-                } else {
-                    self.player.direction = Direction::Right
-                }
+        self.steps += 1;
+        if self.player.direction != Direction::from(action) {
+            self.turns += 1;
+        }
+
+        let before = self.player;
+        let transition = self.transition(before, action);
+        self.player = transition.player;
+        self.cost += transition.cost;
+
+        if transition.blocked {
+            debug!(position = ?before.position, direction = ?before.direction, "move blocked");
+        }
+        if self.player.position != before.position {
+            self.visits[self.player.position] += 1;
+        }
+        for color in self.player.inventory.keys() {
+            if !before.inventory.has(color) {
+                debug!(color = color.name(), "picked up key");
             }
-            Action::Left => {
-                if self.player.direction == Direction::Left {
-                    if self.player.position.x > 0 {
-                        let tile = &self.tiles[self.player.position.x - 1][self.player.position.y];
-                        if tile.tile_type != TileType::Wall {
-                            self.player.position.x -= 1;
-                            self.check_goal();
-                        }
-                    }
-                } else {
-                    self.player.direction = Direction::Left
-                }
+        }
+        for (index, &(position, item)) in self.items.iter().enumerate() {
+            if (self.player.collected & !before.collected) & (1 << index) != 0 {
+                debug!(
+                    item = item.name(),
+                    ?position,
+                    score = self.score(),
+                    "picked up item"
+                );
             }
         }
+        self.check_goal();
+        if !self.is_over() && self.is_dead(&self.player) {
+            self.outcome = Outcome::Died;
+        }
+        if !self.is_over() && self.move_enemies() {
+            debug!(position = ?self.player.position, "caught by an enemy");
+            self.outcome = Outcome::Died;
+        }
+        if !self.is_over() {
+            self.update_walls();
+        }
+
+        // Running out of steps ends the episode without reaching the goal
+        if !self.is_over()
+            && self
+                .max_steps
+                .is_some_and(|max_steps| self.steps >= max_steps)
+        {
+            self.outcome = Outcome::TimedOut;
+        }
+
+        if self.is_over() {
+            info!(
+                outcome = ?self.outcome,
+                steps = self.steps,
+                turns = self.turns,
+                cost = self.cost,
+                "episode ended"
+            );
+        }
     }
 
     fn check_goal(&mut self) {
-        if self.player.position.x == self.goal.x && self.player.position.y == self.goal.y {
-            self.game_over = true;
-            println!("Goal reached! Game over!");
+        if self.is_won(&self.player) {
+            self.outcome = Outcome::Success;
         }
     }
 
-    pub fn reset(&mut self) {
-        *self = GameState::new(self.width, self.height);
+    // Editing helpers keep the player and goal on sensible tiles, refusing
+    // changes that would break that
+
+    pub fn set_tile(&mut self, position: Position, tile_type: TileType) -> bool {
+        if !self.tiles.contains(position)
+            || tile_type == TileType::Goal
+            || self.is_goal(position)
+            // So are the items
+            || self.item_at(position).is_some()
+            // Pairs are fixed when the maze is built
+            || matches!(tile_type, TileType::Teleporter(_))
+            || matches!(self.tiles[position].tile_type, TileType::Teleporter(_))
+            // The schedule owns dynamic walls
+            || self.is_dynamic_wall(position)
+            || (position == self.player.position && tile_type == TileType::Wall)
+        {
+            return false;
+        }
+
+        self.tiles[position].tile_type = tile_type;
+        self.revision += 1;
+        // Only plain walkable tiles carry terrain, like the generator leaves them
+        if tile_type != TileType::Walkable {
+            self.tiles[position].terrain = Terrain::default();
+        }
+        true
+    }
+
+    pub fn set_goal(&mut self, position: Position) -> bool {
+        if !self.tiles.contains(position)
+            || position == self.player.position
+            || self.extra_goals.contains(&position)
+            || self.item_at(position).is_some()
+            || matches!(self.tiles[position].tile_type, TileType::Teleporter(_))
+            || self.is_dynamic_wall(position)
+        {
+            return false;
+        }
+
+        self.tiles[self.goal].tile_type = TileType::Walkable;
+        self.tiles[position].tile_type = TileType::Goal;
+        self.tiles[position].terrain = Terrain::default();
+        self.goal = position;
+        self.revision += 1;
+        true
+    }
+
+    pub fn set_player(&mut self, position: Position) -> bool {
+        if !self.tiles.contains(position)
+            || self.is_goal(position)
+            || self.item_at(position).is_some()
+            || matches!(self.tiles[position].tile_type, TileType::Teleporter(_))
+            || self.is_dynamic_wall(position)
+        {
+            return false;
+        }
+
+        if self.tiles[position].tile_type == TileType::Wall {
+            self.tiles[position].tile_type = TileType::Walkable;
+            self.revision += 1;
+        }
+        // Moving the player starts the episode over from there
+        self.start = Player {
+            position,
+            direction: self.player.direction,
+            inventory: Inventory::default(),
+            health: self.start.health,
+            reached: 0,
+            collected: 0,
+        };
+        self.restart();
+        true
+    }
+
+    // Builder with this game's settings, minus the seed
+    pub fn config(&self) -> GameStateBuilder {
+        Self::builder()
+            .size(self.width, self.height)
+            .generator(self.generator)
+            .view_range(self.view_range)
+            .max_steps(self.max_steps)
+            .doors(self.doors)
+            .teleporters(self.teleporters.len())
+            .terrain(self.terrain)
+            .slippery(self.slippery)
+            .hazards(self.hazards)
+            .hazards_visible(self.hazards_visible)
+            .health(self.start.health)
+            .enemies(self.start_enemies.len())
+            .dynamic_walls(self.dynamic_walls.len())
+            .goals(self.goal_count())
+            .goal_mode(self.goal_mode)
+            .items(self.items.len())
+            .end_condition(self.end_condition)
+    }
+
+    // Same maze and start, with the episode's progress cleared
+    pub fn restart(&mut self) {
+        self.player = self.start;
+        self.enemies = self.start_enemies.clone();
+        let closed: Vec<bool> = self
+            .dynamic_walls
+            .iter()
+            .map(|wall| wall.closed_at_start)
+            .collect();
+        self.set_walls(&closed);
+        self.outcome = Outcome::InProgress;
+        self.steps = 0;
+        self.turns = 0;
+        self.cost = 0;
+        self.visits.fill(0);
+        self.visits[self.player.position] = 1;
+        info!(seed = self.seed, "episode restarted");
+    }
+
+    // A new maze with the same settings, the current one stays if that fails
+    pub fn reset(&mut self) -> Result<(), ConfigError> {
+        *self = self.config().build()?;
+        Ok(())
+    }
+
+    pub fn max_visits(&self) -> u32 {
+        self.visits
+            .iter()
+            .map(|(_, &visits)| visits)
+            .max()
+            .unwrap_or(0)
     }
 
     pub fn get_observation(&self) -> Observation {
         let mut visible_tiles = Vec::new();
-        let (dx, dy) = match self.player.direction {
-            Direction::Up => (0, -1),
-            Direction::Down => (0, 1),
-            Direction::Left => (-1, 0),
-            Direction::Right => (1, 0),
-        };
+        let mut visible_terrain = Vec::new();
+        let mut visible_enemies = Vec::new();
+        let mut visible_items = Vec::new();
+        let (dx, dy) = self.player.direction.delta();
         let mut x = self.player.position.x as isize;
         let mut y = self.player.position.y as isize;
 
-        for _ in 0..3 {
+        for _ in 0..self.view_range {
             x += dx;
             y += dy;
 
             if x < 0 || y < 0 || x >= self.width as isize || y >= self.height as isize {
                 visible_tiles.push(TileType::Wall);
+                visible_terrain.push(Terrain::default());
                 break;
             }
 
-            let tile = &self.tiles[x as usize][y as usize];
-            visible_tiles.push(tile.tile_type);
+            let position = Position {
+                x: x as usize,
+                y: y as usize,
+            };
+            if self.enemies.iter().any(|enemy| enemy.position == position) {
+                visible_enemies.push(visible_tiles.len());
+            }
+            if let Some(index) = self.item_at(position)
+                && self.player.collected & (1 << index) == 0
+            {
+                visible_items.push((visible_tiles.len(), self.items[index].1));
+            }
+            let tile = &self.tiles[position];
+            visible_tiles.push(if tile.tile_type.is_hazard() && !self.hazards_visible {
+                TileType::Walkable
+            } else {
+                tile.tile_type
+            });
+            visible_terrain.push(tile.terrain);
 
-            if tile.tile_type == TileType::Wall {
+            if tile.tile_type.is_opaque() {
                 break;
             }
         }
 
         Observation {
+            position: self.player.position,
             direction: self.player.direction,
             visible_tiles,
+            visible_terrain,
+            visible_enemies,
+            visible_items,
+            terrain: self.tiles[self.player.position].terrain,
+            inventory: self.player.inventory,
+            health: self.player.health,
+            goals_reached: self.player.reached.count_ones() as usize,
+            goals_needed: self.goals_needed(),
+            score: self.score(),
+            items_left: self.items_left(&self.player),
         }
     }
 }
+
+// Randomness for enemies and walls that only depends on the seed, the step and
+// which of them asks, so undoing and replaying an episode gives the same moves
+fn step_roll(seed: u64, step: usize, index: usize) -> u64 {
+    // splitmix64
+    let mut z = seed
+        .wrapping_add((step as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15))
+        .wrapping_add((index as u64).wrapping_mul(0xbf58_476d_1ce4_e5b9));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
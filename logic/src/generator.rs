@@ -0,0 +1,562 @@
+use std::collections::VecDeque;
+
+use rand::prelude::*;
+use tracing::instrument;
+
+use crate::dynamics::{DynamicWall, WallSchedule};
+use crate::enemy::{Enemy, EnemyBehavior};
+use crate::game_state::GameState;
+use crate::grid::Grid;
+use crate::solver::is_solvable;
+use crate::types::{Direction, Inventory, Item, KeyColor, Position, Terrain, Tile, TileType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    // Every tile is independently a wall with the given probability
+    Random { wall_density: f64 },
+    // Perfect maze carved by a randomized depth-first search
+    Backtracker,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Generator::Random { wall_density: 0.2 }
+    }
+}
+
+impl Generator {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Generator::Random { .. } => "random",
+            Generator::Backtracker => "backtracker",
+        }
+    }
+
+    #[instrument(level = "debug", skip(self, rng), fields(generator = self.name()))]
+    pub fn generate<R: Rng>(&self, width: usize, height: usize, rng: &mut R) -> Grid<Tile> {
+        match *self {
+            Generator::Random { wall_density } => Self::random(width, height, wall_density, rng),
+            Generator::Backtracker => Self::backtracker(width, height, rng),
+        }
+    }
+
+    fn random<R: Rng>(width: usize, height: usize, wall_density: f64, rng: &mut R) -> Grid<Tile> {
+        Grid::from_fn(width, height, |_| Tile {
+            tile_type: if rng.random_bool(wall_density) {
+                TileType::Wall
+            } else {
+                TileType::Walkable
+            },
+            terrain: Terrain::default(),
+        })
+    }
+
+    fn backtracker<R: Rng>(width: usize, height: usize, rng: &mut R) -> Grid<Tile> {
+        let mut tiles = Grid::new(
+            width,
+            height,
+            Tile {
+                tile_type: TileType::Wall,
+                terrain: Terrain::default(),
+            },
+        );
+        if tiles.is_empty() {
+            return tiles;
+        }
+
+        // Cells live on even coordinates, the odd ones in between are the walls we knock down
+        let mut stack = vec![Position { x: 0, y: 0 }];
+        tiles[Position { x: 0, y: 0 }].tile_type = TileType::Walkable;
+
+        while let Some(&cell) = stack.last() {
+            let unvisited: Vec<(Position, Position)> = Direction::ALL
+                .iter()
+                .filter_map(|&direction| {
+                    let between = tiles.neighbour(cell, direction)?;
+                    let next = tiles.neighbour(between, direction)?;
+                    (tiles[next].tile_type == TileType::Wall).then_some((between, next))
+                })
+                .collect();
+
+            match unvisited.choose(rng) {
+                Some(&(between, next)) => {
+                    tiles[between].tile_type = TileType::Walkable;
+                    tiles[next].tile_type = TileType::Walkable;
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+
+        tiles
+    }
+}
+
+// Puts doors along the shortest path from start to goal, the first nearest the
+// start, and each door's key where it can be reached with only the earlier keys.
+// Collecting the keys in order always leads to the goal, so a solvable maze stays
+// solvable. Returns how many pairs fit, which can be fewer than asked for.
+pub fn place_doors<R: Rng>(
+    tiles: &mut Grid<Tile>,
+    start: Position,
+    goal: Position,
+    count: usize,
+    rng: &mut R,
+) -> usize {
+    let Some(path) = shortest_path(tiles, start, goal) else {
+        return 0;
+    };
+    // Start and goal stay free
+    let interior = &path[1..path.len() - 1];
+    let count = count.min(interior.len()).min(KeyColor::ALL.len());
+
+    // Spread evenly along the path, the indices are distinct since count <= len
+    let doors: Vec<Position> = (0..count)
+        .map(|i| interior[(i + 1) * interior.len() / (count + 1)])
+        .collect();
+    for (&door, color) in doors.iter().zip(KeyColor::ALL) {
+        tiles[door].tile_type = TileType::Door(color);
+    }
+
+    let mut inventory = Inventory::default();
+    for (i, color) in KeyColor::ALL.into_iter().take(count).enumerate() {
+        let candidates: Vec<Position> = reachable(tiles, start, inventory)
+            .into_iter()
+            .filter(|&position| {
+                position != start
+                    && position != goal
+                    && tiles[position].tile_type == TileType::Walkable
+            })
+            .collect();
+
+        let Some(&key) = candidates.choose(rng) else {
+            // Nowhere to hide this key, so this door and the ones after it go
+            for &door in &doors[i..] {
+                tiles[door].tile_type = TileType::Walkable;
+            }
+            return i;
+        };
+        tiles[key].tile_type = TileType::Key(color);
+        inventory.insert(color);
+    }
+
+    count
+}
+
+fn shortest_path(tiles: &Grid<Tile>, start: Position, goal: Position) -> Option<Vec<Position>> {
+    let mut previous: Grid<Option<Position>> = Grid::new(tiles.width(), tiles.height(), None);
+    let mut queue = VecDeque::from([start]);
+    previous[start] = Some(start);
+
+    while let Some(position) = queue.pop_front() {
+        if position == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+            while current != start {
+                current = previous[current]?;
+                path.push(current);
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for (_, next) in tiles.neighbours(position) {
+            if previous[next].is_none() && tiles[next].tile_type != TileType::Wall {
+                previous[next] = Some(position);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+fn reachable(tiles: &Grid<Tile>, start: Position, inventory: Inventory) -> Vec<Position> {
+    let mut seen = Grid::new(tiles.width(), tiles.height(), false);
+    let mut stack = vec![start];
+    let mut found = Vec::new();
+    seen[start] = true;
+
+    while let Some(position) = stack.pop() {
+        found.push(position);
+        for (_, next) in tiles.neighbours(position) {
+            if !seen[next] && !tiles[next].tile_type.blocks(inventory) {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+
+    found
+}
+
+// Pairs up dead ends, the only tiles no path has to pass through, so a solvable
+// maze stays solvable. Returns how many pairs fit.
+pub fn place_teleporters<R: Rng>(
+    tiles: &mut Grid<Tile>,
+    start: Position,
+    count: usize,
+    rng: &mut R,
+) -> usize {
+    let dead_ends: Vec<Position> = tiles
+        .iter()
+        .filter(|&(position, tile)| {
+            position != start
+                && tile.tile_type == TileType::Walkable
+                && tiles
+                    .neighbours(position)
+                    .filter(|&(_, next)| tiles[next].tile_type != TileType::Wall)
+                    .count()
+                    == 1
+        })
+        .map(|(position, _)| position)
+        .collect();
+
+    let count = count.min(dead_ends.len() / 2);
+    for (id, pair) in dead_ends
+        .choose_multiple(rng, count * 2)
+        .collect::<Vec<_>>()
+        .chunks(2)
+        .enumerate()
+    {
+        for &&position in pair {
+            tiles[position].tile_type = TileType::Teleporter(id as u8);
+        }
+    }
+
+    count
+}
+
+// Extra goals on walkable tiles the player can get to once they hold every key,
+// in a random checkpoint order. Returns where they went, which can be fewer
+// than asked for.
+pub fn place_goals<R: Rng>(
+    tiles: &mut Grid<Tile>,
+    start: Position,
+    count: usize,
+    rng: &mut R,
+) -> Vec<Position> {
+    let mut inventory = Inventory::default();
+    for color in KeyColor::ALL {
+        inventory.insert(color);
+    }
+    let candidates: Vec<Position> = reachable(tiles, start, inventory)
+        .into_iter()
+        .filter(|&position| position != start && tiles[position].tile_type == TileType::Walkable)
+        .collect();
+
+    let goals: Vec<Position> = candidates.choose_multiple(rng, count).copied().collect();
+    for &goal in &goals {
+        tiles[goal] = Tile {
+            tile_type: TileType::Goal,
+            terrain: Terrain::default(),
+        };
+    }
+    goals
+}
+
+// One in this many items is a gem, the rest are coins
+const GEM_RARITY: u32 = 4;
+
+// Coins and gems on plain walkable tiles the player can get to once they hold
+// every key, keeping clear of the ones already there. Only tiles without
+// terrain get one, so the map file can still spell every tile.
+pub fn place_items<R: Rng>(
+    tiles: &Grid<Tile>,
+    start: Position,
+    existing: &[(Position, Item)],
+    count: usize,
+    rng: &mut R,
+) -> Vec<(Position, Item)> {
+    let mut inventory = Inventory::default();
+    for color in KeyColor::ALL {
+        inventory.insert(color);
+    }
+    let candidates: Vec<Position> = reachable(tiles, start, inventory)
+        .into_iter()
+        .filter(|&position| {
+            position != start
+                && tiles[position].tile_type == TileType::Walkable
+                && tiles[position].terrain == Terrain::Plain
+                && !existing.iter().any(|&(taken, _)| taken == position)
+        })
+        .collect();
+
+    candidates
+        .choose_multiple(rng, count)
+        .map(|&position| {
+            let item = if rng.random_ratio(1, GEM_RARITY) {
+                Item::Gem
+            } else {
+                Item::Coin
+            };
+            (position, item)
+        })
+        .collect()
+}
+
+// Tiles of maze per patch of mud or water, and per stretch of road
+const TERRAIN_SPARSITY: usize = 40;
+const MAX_PATCH_RADIUS: usize = 3;
+const MAX_ROAD_LENGTH: usize = 12;
+
+// Scatters round patches of mud and water and straight stretches of road.
+// Only plain walkable tiles change, so the map file can still spell every tile.
+pub fn place_terrain<R: Rng>(tiles: &mut Grid<Tile>, start: Position, rng: &mut R) {
+    let candidates: Vec<Position> = tiles
+        .iter()
+        .filter(|&(position, tile)| position != start && tile.tile_type == TileType::Walkable)
+        .map(|(position, _)| position)
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+
+    let features = (tiles.len() / TERRAIN_SPARSITY).max(1);
+    for _ in 0..features {
+        let &center = candidates.choose(rng).unwrap();
+        let terrain = *[Terrain::Road, Terrain::Mud, Terrain::Water]
+            .choose(rng)
+            .unwrap();
+
+        let covered: Vec<Position> = if terrain == Terrain::Road {
+            let direction = *Direction::ALL.choose(rng).unwrap();
+            let length = rng.random_range(2..=MAX_ROAD_LENGTH);
+            std::iter::successors(Some(center), |&position| {
+                tiles.neighbour(position, direction)
+            })
+            .take(length)
+            .take_while(|&position| tiles[position].tile_type != TileType::Wall)
+            .collect()
+        } else {
+            let radius = rng.random_range(1..=MAX_PATCH_RADIUS);
+            diamond(tiles, center, radius)
+        };
+
+        for position in covered {
+            if position != start && tiles[position].tile_type == TileType::Walkable {
+                tiles[position].terrain = terrain;
+            }
+        }
+    }
+}
+
+// Tiles within `radius` steps of the center, ignoring walls
+fn diamond<T>(tiles: &Grid<T>, center: Position, radius: usize) -> Vec<Position> {
+    let rows = center.y.saturating_sub(radius)..=(center.y + radius).min(tiles.height() - 1);
+    let columns = center.x.saturating_sub(radius)..=(center.x + radius).min(tiles.width() - 1);
+    rows.flat_map(|y| columns.clone().map(move |x| Position { x, y }))
+        .filter(|position| position.x.abs_diff(center.x) + position.y.abs_diff(center.y) <= radius)
+        .collect()
+}
+
+const MAX_ICE_RADIUS: usize = 2;
+const MAX_BELT_LENGTH: usize = 6;
+// Failed placements allowed per requested feature before giving up
+const FEATURE_ATTEMPTS: usize = 10;
+
+// Places `count` features picked by `feature` on plain walkable tiles, dropping
+// any that would make a solvable maze unsolvable. Returns how many were kept.
+fn place_features<R: Rng>(
+    game_state: &mut GameState,
+    count: usize,
+    rng: &mut R,
+    mut feature: impl FnMut(&Grid<Tile>, Position, &mut R) -> (TileType, Vec<Position>),
+) -> usize {
+    let start = game_state.player.position;
+    let items: Vec<Position> = game_state
+        .items
+        .iter()
+        .map(|&(position, _)| position)
+        .collect();
+    let is_free = |tiles: &Grid<Tile>, position: Position| {
+        position != start
+            && tiles[position].tile_type == TileType::Walkable
+            && tiles[position].terrain == Terrain::Plain
+            && !items.contains(&position)
+    };
+    let candidates: Vec<Position> = game_state
+        .tiles
+        .positions()
+        .filter(|&position| is_free(&game_state.tiles, position))
+        .collect();
+    if candidates.is_empty() {
+        return 0;
+    }
+
+    let solvable = is_solvable(game_state);
+    let mut placed = 0;
+    for _ in 0..count * FEATURE_ATTEMPTS {
+        if placed == count {
+            break;
+        }
+
+        let &center = candidates.choose(rng).unwrap();
+        let (tile_type, covered) = feature(&game_state.tiles, center, rng);
+        let covered: Vec<Position> = covered
+            .into_iter()
+            .filter(|&position| is_free(&game_state.tiles, position))
+            .collect();
+        for &position in &covered {
+            game_state.tiles[position].tile_type = tile_type;
+        }
+
+        if solvable && !is_solvable(game_state) {
+            for &position in &covered {
+                game_state.tiles[position].tile_type = TileType::Walkable;
+            }
+        } else {
+            placed += 1;
+        }
+    }
+
+    placed
+}
+
+// Ice patches and conveyor belts
+pub fn place_slippery<R: Rng>(game_state: &mut GameState, count: usize, rng: &mut R) -> usize {
+    place_features(game_state, count, rng, |tiles, center, rng| {
+        if rng.random_bool(0.5) {
+            let radius = rng.random_range(1..=MAX_ICE_RADIUS);
+            (TileType::Ice, diamond(tiles, center, radius))
+        } else {
+            let direction = *Direction::ALL.choose(rng).unwrap();
+            let length = rng.random_range(2..=MAX_BELT_LENGTH);
+            let covered = std::iter::successors(Some(center), |&position| {
+                tiles.neighbour(position, direction)
+            })
+            .take(length)
+            .collect();
+            (TileType::Conveyor(direction), covered)
+        }
+    })
+}
+
+// Single pits and spikes
+pub fn place_hazards<R: Rng>(game_state: &mut GameState, count: usize, rng: &mut R) -> usize {
+    place_features(game_state, count, rng, |_, center, rng| {
+        let tile_type = if rng.random_bool(0.5) {
+            TileType::Pit
+        } else {
+            TileType::Spikes
+        };
+        (tile_type, vec![center])
+    })
+}
+
+// Enemies spawn at least this many steps from the player
+const MIN_ENEMY_DISTANCE: usize = 6;
+const MAX_PATROL_LENGTH: usize = 6;
+
+// Patrollers, random walkers and chasers in equal measure. Nothing stops them
+// from making a maze unwinnable. Returns how many found room.
+pub fn place_enemies<R: Rng>(game_state: &mut GameState, count: usize, rng: &mut R) -> usize {
+    let start = game_state.player.position;
+    let tiles = &game_state.tiles;
+    let candidates: Vec<Position> = tiles
+        .positions()
+        .filter(|&position| {
+            !game_state.is_goal(position)
+                && position.x.abs_diff(start.x) + position.y.abs_diff(start.y) >= MIN_ENEMY_DISTANCE
+                && Enemy::can_enter(tiles, position)
+        })
+        .collect();
+
+    let enemies: Vec<Enemy> = candidates
+        .choose_multiple(rng, count)
+        .map(|&position| match rng.random_range(0..3) {
+            0 => {
+                let direction = *Direction::ALL.choose(rng).unwrap();
+                let length = rng.random_range(2..=MAX_PATROL_LENGTH);
+                let route: Vec<Position> = std::iter::successors(Some(position), |&position| {
+                    tiles.neighbour(position, direction)
+                })
+                .take(length)
+                .take_while(|&position| Enemy::can_enter(tiles, position))
+                .collect();
+                if route.len() < 2 {
+                    Enemy {
+                        position,
+                        behavior: EnemyBehavior::RandomWalk,
+                    }
+                } else {
+                    Enemy::patrol(route)
+                }
+            }
+            1 => Enemy {
+                position,
+                behavior: EnemyBehavior::RandomWalk,
+            },
+            _ => Enemy {
+                position,
+                behavior: EnemyBehavior::Chaser,
+            },
+        })
+        .collect();
+
+    let placed = enemies.len();
+    game_state.start_enemies = enemies.clone();
+    game_state.enemies = enemies;
+    placed
+}
+
+const MIN_WALL_PERIOD: usize = 3;
+const MAX_WALL_PERIOD: usize = 8;
+const MAX_WALL_FLIP_PROBABILITY: f64 = 0.3;
+
+// Turns walls between two open tiles into dynamic ones, half on a timer and half
+// flipping at random. Closed they are the maze as generated and open they only
+// add shortcuts, so solvability never changes. Walls next to ice or a conveyor
+// are left alone, opening one could carry a slide on into a pit. Returns how
+// many were placed.
+pub fn place_dynamic_walls<R: Rng>(game_state: &mut GameState, count: usize, rng: &mut R) -> usize {
+    let tiles = &game_state.tiles;
+    let candidates: Vec<Position> = tiles
+        .positions()
+        .filter(|&position| {
+            tiles[position].tile_type == TileType::Wall
+                && tiles
+                    .neighbours(position)
+                    .filter(|&(_, next)| tiles[next].tile_type != TileType::Wall)
+                    .count()
+                    >= 2
+                && !tiles.neighbours(position).any(|(_, next)| {
+                    matches!(tiles[next].tile_type, TileType::Ice | TileType::Conveyor(_))
+                })
+        })
+        .collect();
+
+    let walls: Vec<DynamicWall> = candidates
+        .choose_multiple(rng, count)
+        .map(|&position| {
+            if rng.random_bool(0.5) {
+                let period = rng.random_range(MIN_WALL_PERIOD..=MAX_WALL_PERIOD);
+                let offset = rng.random_range(0..2 * period);
+                DynamicWall {
+                    position,
+                    schedule: WallSchedule::Periodic { period, offset },
+                    closed_at_start: (offset / period).is_multiple_of(2),
+                }
+            } else {
+                DynamicWall {
+                    position,
+                    schedule: WallSchedule::Random {
+                        probability: rng.random_range(0.05..MAX_WALL_FLIP_PROBABILITY),
+                    },
+                    closed_at_start: true,
+                }
+            }
+        })
+        .collect();
+
+    for wall in &walls {
+        if !wall.closed_at_start {
+            game_state.tiles[wall.position].tile_type = TileType::Walkable;
+        }
+    }
+    let placed = walls.len();
+    game_state.dynamic_walls = walls;
+    placed
+}
@@ -0,0 +1,153 @@
+use std::ops::{Index, IndexMut};
+
+use crate::types::{Direction, Position};
+
+// Row-major storage, so walking a row touches contiguous memory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.cells.fill(value);
+    }
+}
+
+impl<T> Grid<T> {
+    pub fn from_fn(width: usize, height: usize, mut f: impl FnMut(Position) -> T) -> Self {
+        let cells = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Position { x, y }))
+            .map(&mut f)
+            .collect();
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, position: Position) -> bool {
+        position.x < self.width && position.y < self.height
+    }
+
+    pub fn index_of(&self, position: Position) -> usize {
+        position.y * self.width + position.x
+    }
+
+    pub fn position_of(&self, index: usize) -> Position {
+        Position {
+            x: index % self.width,
+            y: index / self.width,
+        }
+    }
+
+    pub fn get(&self, position: Position) -> Option<&T> {
+        if self.contains(position) {
+            self.cells.get(self.index_of(position))
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, position: Position) -> Option<&mut T> {
+        if self.contains(position) {
+            let index = self.index_of(position);
+            self.cells.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    pub fn neighbour(&self, position: Position, direction: Direction) -> Option<Position> {
+        position.neighbour(direction, self.width, self.height)
+    }
+
+    pub fn neighbours(&self, position: Position) -> impl Iterator<Item = (Direction, Position)> {
+        let (width, height) = (self.width, self.height);
+        Direction::ALL.into_iter().filter_map(move |direction| {
+            position
+                .neighbour(direction, width, height)
+                .map(|neighbour| (direction, neighbour))
+        })
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> + use<T> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| Position { x, y }))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Position, &T)> {
+        self.positions().zip(self.cells.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Position, &mut T)> {
+        self.positions().zip(self.cells.iter_mut())
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+}
+
+impl<T> Index<Position> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Position) -> &T {
+        assert!(
+            self.contains(position),
+            "position {position:?} outside {}x{} grid",
+            self.width,
+            self.height
+        );
+        &self.cells[self.index_of(position)]
+    }
+}
+
+impl<T> IndexMut<Position> for Grid<T> {
+    fn index_mut(&mut self, position: Position) -> &mut T {
+        assert!(
+            self.contains(position),
+            "position {position:?} outside {}x{} grid",
+            self.width,
+            self.height
+        );
+        let index = self.index_of(position);
+        &mut self.cells[index]
+    }
+}
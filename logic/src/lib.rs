@@ -1,5 +1,25 @@
-pub mod types;
+pub mod agent;
+pub mod builder;
+pub mod dynamics;
+pub mod enemy;
 pub mod game_state;
+pub mod generator;
+pub mod grid;
+pub mod map_file;
+pub mod replanner;
+pub mod search;
+pub mod solver;
+pub mod types;
 
-pub use types::*;
+pub use agent::*;
+pub use builder::*;
+pub use dynamics::*;
+pub use enemy::*;
 pub use game_state::*;
+pub use generator::*;
+pub use grid::*;
+pub use map_file::*;
+pub use replanner::*;
+pub use search::*;
+pub use solver::*;
+pub use types::*;
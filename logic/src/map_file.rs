@@ -0,0 +1,395 @@
+// Plain text maps, one character per tile:
+//
+//   #  wall
+//   .  walkable
+//   = , ~  road, mud and water, walkable with a different cost
+//   G  goal
+//   ①-⑳  goals numbered in checkpoint order, for mazes with several. Any G
+//        tiles come after them in reading order.
+//   r b y p  red, blue, yellow and purple keys
+//   R B Y P  the matching doors
+//   0-9      teleporters, each digit used by exactly two tiles
+//   *        ice
+//   O        pit
+//   x        spikes
+//   $ %      coin and gem, lying on plain walkable ground
+//   ↑ ↓ ← →  conveyors, pushing the way the arrow points
+//   ^ v < >  player, facing the arrow's direction
+//
+// Every row must have the same length. Start and goal are optional and get
+// picked at random when missing.
+//
+// Settings that differ from the defaults go above the tiles, one per line:
+//
+//   goal_mode: any, all or ordered
+//   end_condition: goal or collect_all
+
+use std::fs;
+use std::path::Path;
+
+use crate::builder::{ConfigError, GameStateBuilder};
+use crate::game_state::GameState;
+use crate::grid::Grid;
+use crate::types::{
+    Direction, EndCondition, GoalMode, Item, KeyColor, Position, Terrain, Tile, TileType,
+};
+
+pub fn parse_map(map: &str) -> Result<GameStateBuilder, ConfigError> {
+    let rows: Vec<&str> = map
+        .lines()
+        .map(|line| line.trim_end_matches('\r'))
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    // No tile is spelled with a colon, so those lines are settings
+    let settings = rows.iter().take_while(|row| row.contains(':')).count();
+    let mut goal_mode = GoalMode::default();
+    let mut end_condition = EndCondition::default();
+    for (index, row) in rows[..settings].iter().enumerate() {
+        let line = index + 1;
+        let (name, value) = row.split_once(':').unwrap();
+        let value = value.trim();
+        match name.trim() {
+            "goal_mode" => {
+                goal_mode = GoalMode::ALL
+                    .into_iter()
+                    .find(|mode| mode.name() == value)
+                    .ok_or_else(|| ConfigError::Parse {
+                        line,
+                        message: format!("unknown goal mode '{value}'"),
+                    })?;
+            }
+            "end_condition" => {
+                end_condition = EndCondition::ALL
+                    .into_iter()
+                    .find(|condition| condition.name() == value)
+                    .ok_or_else(|| ConfigError::Parse {
+                        line,
+                        message: format!("unknown end condition '{value}'"),
+                    })?;
+            }
+            other => {
+                return Err(ConfigError::Parse {
+                    line,
+                    message: format!("unknown setting '{other}'"),
+                });
+            }
+        }
+    }
+    let rows = &rows[settings..];
+
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    let height = rows.len();
+    let mut cells = Vec::with_capacity(width * height);
+    let mut start = None;
+    let mut goals = Vec::new();
+    let mut checkpoints: Vec<(u32, Position)> = Vec::new();
+    let mut items = Vec::new();
+
+    for (y, row) in rows.iter().enumerate() {
+        let line = settings + y + 1;
+        if row.chars().count() != width {
+            return Err(ConfigError::Parse {
+                line,
+                message: format!("expected {width} tiles, found {}", row.chars().count()),
+            });
+        }
+
+        for (x, symbol) in row.chars().enumerate() {
+            let position = Position { x, y };
+            let direction = match symbol {
+                '^' => Some(Direction::Up),
+                'v' => Some(Direction::Down),
+                '<' => Some(Direction::Left),
+                '>' => Some(Direction::Right),
+                _ => None,
+            };
+            let key = key_color(symbol);
+            let terrain = match symbol {
+                '=' => Terrain::Road,
+                ',' => Terrain::Mud,
+                '~' => Terrain::Water,
+                _ => Terrain::Plain,
+            };
+
+            let tile_type = match symbol {
+                '#' => TileType::Wall,
+                '.' | '=' | ',' | '~' => TileType::Walkable,
+                _ if key.is_some() => {
+                    let color = key.unwrap();
+                    if symbol.is_ascii_uppercase() {
+                        TileType::Door(color)
+                    } else {
+                        TileType::Key(color)
+                    }
+                }
+                '0'..='9' => TileType::Teleporter(symbol as u8 - b'0'),
+                '*' => TileType::Ice,
+                'O' => TileType::Pit,
+                'x' => TileType::Spikes,
+                '↑' => TileType::Conveyor(Direction::Up),
+                '↓' => TileType::Conveyor(Direction::Down),
+                '←' => TileType::Conveyor(Direction::Left),
+                '→' => TileType::Conveyor(Direction::Right),
+                'G' => {
+                    goals.push(position);
+                    TileType::Walkable
+                }
+                '①'..='⑳' => {
+                    let number = symbol as u32 - '①' as u32;
+                    if checkpoints.iter().any(|&(other, _)| other == number) {
+                        return Err(ConfigError::Parse {
+                            line,
+                            message: format!("more than one checkpoint {symbol}"),
+                        });
+                    }
+                    checkpoints.push((number, position));
+                    TileType::Walkable
+                }
+                '$' | '%' => {
+                    let item = if symbol == '$' { Item::Coin } else { Item::Gem };
+                    items.push((position, item));
+                    TileType::Walkable
+                }
+                _ if direction.is_some() => {
+                    if start.replace((position, direction.unwrap())).is_some() {
+                        return Err(ConfigError::Parse {
+                            line,
+                            message: "more than one player".to_string(),
+                        });
+                    }
+                    TileType::Walkable
+                }
+                other => {
+                    return Err(ConfigError::Parse {
+                        line,
+                        message: format!("unknown tile '{other}'"),
+                    });
+                }
+            };
+            cells.push(Tile { tile_type, terrain });
+        }
+    }
+
+    checkpoints.sort_by_key(|&(number, _)| number);
+    let goals: Vec<Position> = checkpoints
+        .into_iter()
+        .map(|(_, position)| position)
+        .chain(goals)
+        .collect();
+
+    let mut cells = cells.into_iter();
+    let tiles = Grid::from_fn(width, height, |_| cells.next().unwrap());
+    let mut builder = GameState::builder()
+        .tiles(tiles)
+        .placed_items(items)
+        .goal_mode(goal_mode)
+        .end_condition(end_condition);
+    if let Some((position, direction)) = start {
+        builder = builder.start(position).direction(direction);
+    }
+    if let Some((&goal, extra_goals)) = goals.split_first() {
+        builder = builder.goal(goal).extra_goals(extra_goals.to_vec());
+    }
+
+    Ok(builder)
+}
+
+fn key_color(symbol: char) -> Option<KeyColor> {
+    KeyColor::ALL
+        .into_iter()
+        .find(|&color| key_symbol(color) == symbol.to_ascii_lowercase())
+}
+
+fn key_symbol(color: KeyColor) -> char {
+    match color {
+        KeyColor::Red => 'r',
+        KeyColor::Blue => 'b',
+        KeyColor::Yellow => 'y',
+        KeyColor::Purple => 'p',
+    }
+}
+
+impl GameState {
+    pub fn from_map_str(map: &str) -> Result<GameState, ConfigError> {
+        parse_map(map)?.build()
+    }
+
+    pub fn load_map(path: impl AsRef<Path>) -> Result<GameState, ConfigError> {
+        let path = path.as_ref();
+        let map = fs::read_to_string(path)
+            .map_err(|error| ConfigError::Io(format!("{}: {error}", path.display())))?;
+        Self::from_map_str(&map)
+    }
+
+    // Saves the maze as the episode starts, wherever the player is now
+    pub fn to_map_string(&self) -> String {
+        let mut map = String::with_capacity((self.width + 1) * self.height);
+        if self.goal_mode != GoalMode::default() {
+            map.push_str(&format!("goal_mode: {}\n", self.goal_mode.name()));
+        }
+        if self.end_condition != EndCondition::default() {
+            map.push_str(&format!("end_condition: {}\n", self.end_condition.name()));
+        }
+
+        let items: Vec<(Position, Item)> = self
+            .remaining_items(&self.start)
+            .map(|(_, position, item)| (position, item))
+            .collect();
+        for (position, tile) in self.tiles.iter() {
+            let item = items
+                .iter()
+                .find(|&&(item_position, _)| item_position == position);
+            let symbol = if position == self.start.position {
+                match self.start.direction {
+                    Direction::Up => '^',
+                    Direction::Down => 'v',
+                    Direction::Left => '<',
+                    Direction::Right => '>',
+                }
+            } else if let Some(&(_, item)) = item {
+                match item {
+                    Item::Coin => '$',
+                    Item::Gem => '%',
+                }
+            } else {
+                match tile.tile_type {
+                    TileType::Wall => '#',
+                    TileType::Walkable => match tile.terrain {
+                        Terrain::Road => '=',
+                        Terrain::Plain => '.',
+                        Terrain::Mud => ',',
+                        Terrain::Water => '~',
+                    },
+                    // Numbered so the checkpoint order survives a reload
+                    TileType::Goal if self.goal_count() > 1 => self
+                        .goals()
+                        .position(|goal| goal == position)
+                        .and_then(|index| char::from_u32('①' as u32 + index as u32))
+                        .unwrap_or('G'),
+                    TileType::Goal => 'G',
+                    TileType::Key(color) => key_symbol(color),
+                    TileType::Door(color) => key_symbol(color).to_ascii_uppercase(),
+                    TileType::Teleporter(id) => (b'0' + id) as char,
+                    TileType::Ice => '*',
+                    TileType::Pit => 'O',
+                    TileType::Spikes => 'x',
+                    TileType::Conveyor(Direction::Up) => '↑',
+                    TileType::Conveyor(Direction::Down) => '↓',
+                    TileType::Conveyor(Direction::Left) => '←',
+                    TileType::Conveyor(Direction::Right) => '→',
+                }
+            };
+            map.push(symbol);
+
+            if position.x + 1 == self.width {
+                map.push('\n');
+            }
+        }
+
+        map
+    }
+
+    pub fn save_map(&self, path: impl AsRef<Path>) -> Result<(), ConfigError> {
+        let path = path.as_ref();
+        fs::write(path, self.to_map_string())
+            .map_err(|error| ConfigError::Io(format!("{}: {error}", path.display())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{EndCondition, GoalMode};
+
+    fn parse_error(map: &str) -> Option<ConfigError> {
+        parse_map(map).err()
+    }
+
+    fn parse(line: usize, message: &str) -> Option<ConfigError> {
+        Some(ConfigError::Parse {
+            line,
+            message: message.to_string(),
+        })
+    }
+
+    #[test]
+    fn rejects_malformed_maps() {
+        assert_eq!(
+            parse_error("^.G\n..\n"),
+            parse(2, "expected 3 tiles, found 2")
+        );
+        assert_eq!(parse_error("^.G\n.?.\n"), parse(2, "unknown tile '?'"));
+        assert_eq!(parse_error("^.G\n..>\n"), parse(2, "more than one player"));
+        assert_eq!(
+            parse_error("①.①\n^.G\n"),
+            parse(1, "more than one checkpoint ①")
+        );
+        assert_eq!(
+            parse_error("speed: 3\n^.G\n"),
+            parse(1, "unknown setting 'speed'")
+        );
+        assert_eq!(
+            parse_error("goal_mode: some\n^.G\n"),
+            parse(1, "unknown goal mode 'some'")
+        );
+        assert_eq!(
+            parse_error("goal_mode: all\nend_condition: never\n^.G\n"),
+            parse(2, "unknown end condition 'never'")
+        );
+    }
+
+    #[test]
+    fn reports_a_missing_file() {
+        let error = GameState::load_map("no/such/maze.txt").err();
+        assert!(
+            matches!(error, Some(ConfigError::Io(message)) if message.starts_with("no/such/maze.txt"))
+        );
+    }
+
+    #[test]
+    fn round_trips_through_a_map_file() {
+        let game_state = GameState::builder()
+            .size(21, 15)
+            .doors(2)
+            .teleporters(2)
+            .terrain(true)
+            .slippery(2)
+            .hazards(2)
+            .goals(3)
+            .goal_mode(GoalMode::Ordered)
+            .items(4)
+            .end_condition(EndCondition::CollectAll)
+            .seed(7)
+            .build()
+            .unwrap();
+
+        let map = game_state.to_map_string();
+        let loaded = GameState::from_map_str(&map).unwrap();
+
+        assert_eq!(loaded.to_map_string(), map);
+        let tiles = |game_state: &GameState| -> Vec<(TileType, Terrain)> {
+            game_state
+                .tiles
+                .iter()
+                .map(|(_, tile)| (tile.tile_type, tile.terrain))
+                .collect()
+        };
+        assert_eq!(tiles(&loaded), tiles(&game_state));
+        assert_eq!(loaded.start, game_state.start);
+        assert_eq!(
+            loaded.goals().collect::<Vec<_>>(),
+            game_state.goals().collect::<Vec<_>>()
+        );
+        // Items come back in reading order, which only matters once some are collected
+        let items = |game_state: &GameState| {
+            let mut items = game_state.items.clone();
+            items.sort_by_key(|&(position, _)| (position.y, position.x));
+            items
+        };
+        assert_eq!(items(&loaded), items(&game_state));
+        assert_eq!(loaded.teleporters, game_state.teleporters);
+        assert_eq!(loaded.goal_mode, GoalMode::Ordered);
+        assert_eq!(loaded.end_condition, EndCondition::CollectAll);
+    }
+}
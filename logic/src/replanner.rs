@@ -0,0 +1,386 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use tracing::{debug, instrument};
+
+use crate::game_state::GameState;
+use crate::grid::Grid;
+use crate::types::{Inventory, Position, Terrain, TileType};
+
+// Stands in for infinity, low enough that adding a step cost can't overflow
+const UNREACHABLE: usize = usize::MAX / 2;
+
+type Key = (usize, usize);
+
+// What stepping onto a tile costs, None when the player can't or shouldn't
+pub fn step_cost(tile_type: TileType, terrain: Terrain, inventory: Inventory) -> Option<usize> {
+    (!tile_type.blocks(inventory) && tile_type != TileType::Pit).then(|| terrain.cost())
+}
+
+// D* Lite (Koenig and Likhachev). Searches backwards from the goals and keeps
+// its g and rhs values between calls, so a changed tile only repairs the part
+// of the search that depended on it, and a moving start only shifts the keys.
+// Plans plain steps between neighbours: slides, conveyors and teleporters
+// just put the player somewhere else to plan from.
+pub struct DStarLite {
+    // Any of them will do, swap them with `set_goals` as they get reached
+    goals: Vec<Position>,
+    start: Position,
+    // Believed cost of stepping onto each tile, None for blocked
+    costs: Grid<Option<usize>>,
+    // Cost to the goal as of the last expansion, and as its neighbours say
+    g: Grid<usize>,
+    rhs: Grid<usize>,
+    // Key each tile is queued under, heap entries that don't match are stale
+    queued: Grid<Option<Key>>,
+    heap: BinaryHeap<Reverse<(Key, usize)>>,
+    // Added to every new key as the start moves, so queued keys stay lower bounds
+    km: usize,
+    // Revision, inventory, goals reached and items collected as of the last
+    // sync, None before the first
+    synced: Option<(u64, Inventory, u32, u32)>,
+    // Tiles expanded since construction, to weigh repairs against fresh searches
+    pub expansions: usize,
+}
+
+impl DStarLite {
+    pub fn new(costs: Grid<Option<usize>>, start: Position, goals: Vec<Position>) -> Self {
+        let (width, height) = (costs.width(), costs.height());
+        let mut planner = DStarLite {
+            goals: Vec::new(),
+            start,
+            costs,
+            g: Grid::new(width, height, UNREACHABLE),
+            rhs: Grid::new(width, height, UNREACHABLE),
+            queued: Grid::new(width, height, None),
+            heap: BinaryHeap::new(),
+            km: 0,
+            synced: None,
+            expansions: 0,
+        };
+        planner.set_goals(goals);
+        planner
+    }
+
+    // Knows nothing but the size of the maze and assumes every tile is plain
+    // ground until told otherwise
+    pub fn unknown(width: usize, height: usize, start: Position, goals: Vec<Position>) -> Self {
+        let costs = Grid::new(width, height, Some(Terrain::default().cost()));
+        Self::new(costs, start, goals)
+    }
+
+    // Knows the whole maze, keep it current with `sync`
+    pub fn from_game_state(game_state: &GameState) -> Self {
+        let mut planner = Self::new(
+            Self::costs_of(game_state),
+            game_state.player.position,
+            game_state.targets(&game_state.player),
+        );
+        planner.synced = Some(Self::sync_point(game_state));
+        planner
+    }
+
+    fn costs_of(game_state: &GameState) -> Grid<Option<usize>> {
+        let inventory = game_state.player.inventory;
+        game_state
+            .tiles
+            .map(|tile| step_cost(tile.tile_type, tile.terrain, inventory))
+    }
+
+    fn sync_point(game_state: &GameState) -> (u64, Inventory, u32, u32) {
+        let player = &game_state.player;
+        (
+            game_state.revision,
+            player.inventory,
+            player.reached,
+            player.collected,
+        )
+    }
+
+    pub fn goals(&self) -> &[Position] {
+        &self.goals
+    }
+
+    // Swaps the goals, only the tiles that stop or start being one get queued
+    pub fn set_goals(&mut self, goals: Vec<Position>) {
+        let old = std::mem::replace(&mut self.goals, goals);
+        let changed: Vec<Position> = old
+            .iter()
+            .filter(|goal| !self.goals.contains(goal))
+            .chain(self.goals.iter().filter(|goal| !old.contains(goal)))
+            .copied()
+            .collect();
+        for position in changed {
+            self.update_vertex(position);
+        }
+    }
+
+    pub fn cost(&self, position: Position) -> Option<usize> {
+        self.costs[position]
+    }
+
+    // Changes what one tile costs and queues the neighbours that relied on it
+    pub fn set_cost(&mut self, position: Position, cost: Option<usize>) {
+        if self.costs[position] == cost {
+            return;
+        }
+        self.costs[position] = cost;
+        let neighbours: Vec<Position> = self.costs.neighbours(position).map(|(_, n)| n).collect();
+        for neighbour in neighbours {
+            self.update_vertex(neighbour);
+        }
+    }
+
+    // Picks up tile edits, keys, goals and items from the game, touching only
+    // tiles whose cost changed. Does nothing while none of them changed.
+    pub fn sync(&mut self, game_state: &GameState) {
+        let current = Self::sync_point(game_state);
+        if self.synced == Some(current) {
+            return;
+        }
+        self.synced = Some(current);
+        self.set_goals(game_state.targets(&game_state.player));
+
+        let mut changed = 0;
+        for (position, cost) in Self::costs_of(game_state).iter() {
+            if self.costs[position] != *cost {
+                self.set_cost(position, *cost);
+                changed += 1;
+            }
+        }
+        debug!(changed, revision = game_state.revision, "synced tiles");
+    }
+
+    // Cheapest path from `start` to the nearest goal, both ends included, and
+    // its cost
+    #[instrument(level = "debug", skip(self))]
+    pub fn plan(&mut self, start: Position) -> Option<(Vec<Position>, usize)> {
+        self.move_start(start);
+        self.compute_shortest_path();
+
+        if self.g[start] >= UNREACHABLE {
+            return None;
+        }
+        let mut path = vec![start];
+        let mut total = 0;
+        let mut current = start;
+        while !self.goals.contains(&current) {
+            // Every step lowers g, so a longer path means a stale plan
+            if path.len() > self.costs.len() {
+                return None;
+            }
+            let (next, cost) = self.best_neighbour(current)?;
+            total += cost;
+            path.push(next);
+            current = next;
+        }
+        Some((path, total))
+    }
+
+    // The tile to step onto next, None at the goal or with no way there
+    pub fn next_step(&mut self, start: Position) -> Option<Position> {
+        self.plan(start).and_then(|(path, _)| path.get(1).copied())
+    }
+
+    fn move_start(&mut self, start: Position) {
+        if start != self.start {
+            self.km += self.heuristic(self.start, start);
+            self.start = start;
+        }
+    }
+
+    fn heuristic(&self, a: Position, b: Position) -> usize {
+        (a.x.abs_diff(b.x) + a.y.abs_diff(b.y)) * Terrain::MIN_COST
+    }
+
+    fn key(&self, position: Position) -> Key {
+        let best = self.g[position].min(self.rhs[position]);
+        (
+            best.saturating_add(self.heuristic(self.start, position) + self.km),
+            best,
+        )
+    }
+
+    // The neighbour with the lowest step cost plus cost to go, with that step cost
+    fn best_neighbour(&self, position: Position) -> Option<(Position, usize)> {
+        self.costs
+            .neighbours(position)
+            .filter_map(|(_, next)| Some((next, self.costs[next]?)))
+            .map(|(next, cost)| (next, cost, cost + self.g[next]))
+            .filter(|&(_, _, total)| total < UNREACHABLE)
+            .min_by_key(|&(_, _, total)| total)
+            .map(|(next, cost, _)| (next, cost))
+    }
+
+    fn enqueue(&mut self, position: Position, key: Key) {
+        self.queued[position] = Some(key);
+        self.heap
+            .push(Reverse((key, self.costs.index_of(position))));
+    }
+
+    // Drops stale heap entries and returns the lowest queued tile
+    fn top(&mut self) -> Option<(Key, Position)> {
+        while let Some(&Reverse((key, index))) = self.heap.peek() {
+            let position = self.costs.position_of(index);
+            if self.queued[position] == Some(key) {
+                return Some((key, position));
+            }
+            self.heap.pop();
+        }
+        None
+    }
+
+    fn update_vertex(&mut self, position: Position) {
+        if self.goals.contains(&position) {
+            self.rhs[position] = 0;
+        } else {
+            self.rhs[position] = self
+                .best_neighbour(position)
+                .map_or(UNREACHABLE, |(next, cost)| cost + self.g[next]);
+        }
+        if self.g[position] != self.rhs[position] {
+            let key = self.key(position);
+            self.enqueue(position, key);
+        } else {
+            self.queued[position] = None;
+        }
+    }
+
+    fn compute_shortest_path(&mut self) {
+        while let Some((old_key, position)) = self.top() {
+            if old_key >= self.key(self.start) && self.g[self.start] == self.rhs[self.start] {
+                break;
+            }
+            self.expansions += 1;
+
+            let new_key = self.key(position);
+            if old_key < new_key {
+                self.enqueue(position, new_key);
+                continue;
+            }
+
+            self.queued[position] = None;
+            if self.g[position] > self.rhs[position] {
+                self.g[position] = self.rhs[position];
+            } else {
+                self.g[position] = UNREACHABLE;
+                self.update_vertex(position);
+            }
+            let neighbours: Vec<Position> =
+                self.costs.neighbours(position).map(|(_, n)| n).collect();
+            for neighbour in neighbours {
+                self.update_vertex(neighbour);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::{Search, SearchAlgorithm, SearchStatus};
+
+    // Plain ground only, so every step costs the same and the shortest plan is
+    // the one breadth-first search finds. The wall at (4, 0) hides a shortcut.
+    const MAP: &str = "\
+>...#...
+.##.#.#.
+.#..#.#.
+.#.##.#.
+.#.....G
+........
+";
+    const SHORTCUT: Position = Position { x: 4, y: 0 };
+
+    fn breadth_first_length(game_state: &GameState) -> Option<usize> {
+        let mut search = Search::new(game_state, SearchAlgorithm::BreadthFirst);
+        (search.run(game_state) == SearchStatus::Found).then(|| search.path().len())
+    }
+
+    // Checks the plan walks open neighbours from the start to the goal and
+    // returns how many tiles it covers
+    fn checked_length(game_state: &GameState, planner: &mut DStarLite) -> Option<usize> {
+        let start = game_state.player.position;
+        let (path, cost) = planner.plan(start)?;
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&game_state.goal));
+        for step in path.windows(2) {
+            assert_eq!(
+                step[0].x.abs_diff(step[1].x) + step[0].y.abs_diff(step[1].y),
+                1
+            );
+            assert_ne!(game_state.tiles[step[1]].tile_type, TileType::Wall);
+        }
+        assert_eq!(cost, (path.len() - 1) * Terrain::Plain.cost());
+        Some(path.len())
+    }
+
+    #[test]
+    fn plans_as_short_as_breadth_first_search() {
+        let game_state = GameState::from_map_str(MAP).unwrap();
+        let mut planner = DStarLite::from_game_state(&game_state);
+
+        let length = checked_length(&game_state, &mut planner);
+        assert!(length.is_some());
+        assert_eq!(length, breadth_first_length(&game_state));
+    }
+
+    #[test]
+    fn replans_around_a_closed_tile() {
+        let mut game_state = GameState::from_map_str(MAP).unwrap();
+        let mut planner = DStarLite::from_game_state(&game_state);
+        let (path, _) = planner.plan(game_state.player.position).unwrap();
+        let closed = path[path.len() / 2];
+
+        assert!(game_state.set_tile(closed, TileType::Wall));
+        planner.set_cost(closed, None);
+
+        let length = checked_length(&game_state, &mut planner);
+        assert!(length.is_some());
+        assert_eq!(length, breadth_first_length(&game_state));
+        let (path, _) = planner.plan(game_state.player.position).unwrap();
+        assert!(!path.contains(&closed));
+    }
+
+    #[test]
+    fn replans_through_an_opened_wall() {
+        let mut game_state = GameState::from_map_str(MAP).unwrap();
+        let mut planner = DStarLite::from_game_state(&game_state);
+        let before = checked_length(&game_state, &mut planner).unwrap();
+
+        assert!(game_state.set_tile(SHORTCUT, TileType::Walkable));
+        planner.set_cost(SHORTCUT, Some(Terrain::Plain.cost()));
+
+        let after = checked_length(&game_state, &mut planner).unwrap();
+        assert!(after < before);
+        assert_eq!(Some(after), breadth_first_length(&game_state));
+    }
+
+    #[test]
+    fn closing_the_shortcut_again_restores_the_old_cost() {
+        let game_state = GameState::from_map_str(MAP).unwrap();
+        let mut planner = DStarLite::from_game_state(&game_state);
+        let start = game_state.player.position;
+        let before = planner.plan(start).unwrap();
+
+        planner.set_cost(SHORTCUT, Some(Terrain::Plain.cost()));
+        assert_ne!(planner.plan(start), Some(before.clone()));
+        planner.set_cost(SHORTCUT, None);
+        assert_eq!(planner.plan(start).map(|(_, cost)| cost), Some(before.1));
+    }
+
+    #[test]
+    fn finds_no_plan_once_the_goal_is_cut_off() {
+        let mut game_state = GameState::from_map_str(MAP).unwrap();
+        let mut planner = DStarLite::from_game_state(&game_state);
+        let goal = game_state.goal;
+
+        for (_, neighbour) in game_state.tiles.neighbours(goal).collect::<Vec<_>>() {
+            game_state.set_tile(neighbour, TileType::Wall);
+            planner.set_cost(neighbour, None);
+        }
+
+        assert_eq!(planner.plan(game_state.player.position), None);
+        assert_eq!(breadth_first_length(&game_state), None);
+    }
+}
@@ -0,0 +1,341 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::fmt;
+
+use crate::game_state::GameState;
+use crate::grid::Grid;
+use crate::types::{Action, Direction, Player, Position, Terrain};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchAlgorithm {
+    BreadthFirst,
+    DepthFirst,
+    AStar,
+    Dijkstra,
+    WallFollower,
+}
+
+impl SearchAlgorithm {
+    pub const ALL: [SearchAlgorithm; 5] = [
+        SearchAlgorithm::BreadthFirst,
+        SearchAlgorithm::DepthFirst,
+        SearchAlgorithm::AStar,
+        SearchAlgorithm::Dijkstra,
+        SearchAlgorithm::WallFollower,
+    ];
+}
+
+impl fmt::Display for SearchAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SearchAlgorithm::BreadthFirst => "BFS",
+            SearchAlgorithm::DepthFirst => "DFS",
+            SearchAlgorithm::AStar => "A*",
+            SearchAlgorithm::Dijkstra => "Dijkstra",
+            SearchAlgorithm::WallFollower => "Wall follower",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeState {
+    Unseen,
+    Frontier,
+    Visited,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchStatus {
+    Running,
+    Found,
+    Exhausted,
+}
+
+#[derive(Debug)]
+enum Frontier {
+    Queue(VecDeque<Position>),
+    Stack(Vec<Position>),
+    // Ordered by (priority, insertion order) so equal priorities expand first in first out
+    Heap(BinaryHeap<Reverse<(usize, usize, usize)>>, usize),
+    Walker(Position, Direction),
+}
+
+// A search that advances one expansion per `step`, so front-ends can animate it.
+// Turning is free, unlike `optimal_actions`, and Dijkstra and A* weigh moves by
+// the terrain entered.
+#[derive(Debug)]
+pub struct Search {
+    algorithm: SearchAlgorithm,
+    frontier: Frontier,
+    nodes: Grid<NodeState>,
+    came_from: Grid<Option<Position>>,
+    cost: Grid<usize>,
+    // Wall follower loop detection, one bit per direction
+    walked: Grid<u8>,
+    start: Position,
+    // Goals or items that would count next, the search stops at whichever it
+    // finds first
+    goals: Vec<Position>,
+    status: SearchStatus,
+    path: Vec<Position>,
+    expansions: usize,
+}
+
+impl Search {
+    pub fn new(game_state: &GameState, algorithm: SearchAlgorithm) -> Self {
+        let width = game_state.width;
+        let height = game_state.height;
+        let start = game_state.player.position;
+
+        let frontier = match algorithm {
+            SearchAlgorithm::BreadthFirst => Frontier::Queue(VecDeque::from([start])),
+            SearchAlgorithm::DepthFirst => Frontier::Stack(vec![start]),
+            SearchAlgorithm::AStar | SearchAlgorithm::Dijkstra => {
+                Frontier::Heap(BinaryHeap::new(), 0)
+            }
+            SearchAlgorithm::WallFollower => Frontier::Walker(start, game_state.player.direction),
+        };
+
+        let mut search = Search {
+            algorithm,
+            frontier,
+            nodes: Grid::new(width, height, NodeState::Unseen),
+            came_from: Grid::new(width, height, None),
+            cost: Grid::new(width, height, usize::MAX),
+            walked: Grid::new(width, height, 0),
+            start,
+            goals: game_state.targets(&game_state.player),
+            status: SearchStatus::Running,
+            path: Vec::new(),
+            expansions: 0,
+        };
+
+        search.nodes[start] = NodeState::Frontier;
+        search.cost[start] = 0;
+        if let Frontier::Heap(..) = search.frontier {
+            search.push_heap(start, 0);
+        }
+
+        search
+    }
+
+    pub fn algorithm(&self) -> SearchAlgorithm {
+        self.algorithm
+    }
+
+    pub fn status(&self) -> SearchStatus {
+        self.status
+    }
+
+    pub fn nodes(&self) -> &Grid<NodeState> {
+        &self.nodes
+    }
+
+    // Start to goal, filled in once the goal is found
+    pub fn path(&self) -> &[Position] {
+        &self.path
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    pub fn frontier(&self) -> Vec<Position> {
+        match &self.frontier {
+            Frontier::Queue(queue) => queue.iter().copied().collect(),
+            Frontier::Stack(stack) => stack.clone(),
+            Frontier::Heap(heap, _) => heap
+                .iter()
+                .map(|Reverse((_, _, index))| self.nodes.position_of(*index))
+                .collect(),
+            Frontier::Walker(position, _) => vec![*position],
+        }
+    }
+
+    fn heuristic(&self, position: Position) -> usize {
+        match self.algorithm {
+            // Teleporters can make this overestimate, so A* may miss the
+            // cheapest path on mazes that have them
+            SearchAlgorithm::AStar => {
+                self.goals
+                    .iter()
+                    .map(|goal| position.x.abs_diff(goal.x) + position.y.abs_diff(goal.y))
+                    .min()
+                    .unwrap_or(0)
+                    * Terrain::MIN_COST
+            }
+            _ => 0,
+        }
+    }
+
+    fn push_heap(&mut self, position: Position, cost: usize) {
+        let priority = cost + self.heuristic(position);
+        let index = self.nodes.index_of(position);
+        if let Frontier::Heap(heap, counter) = &mut self.frontier {
+            heap.push(Reverse((priority, *counter, index)));
+            *counter += 1;
+        }
+    }
+
+    fn pop(&mut self) -> Option<Position> {
+        match &mut self.frontier {
+            Frontier::Queue(queue) => queue.pop_front(),
+            Frontier::Stack(stack) => stack.pop(),
+            Frontier::Heap(heap, _) => loop {
+                let Reverse((_, _, index)) = heap.pop()?;
+                let position = self.nodes.position_of(index);
+                // Stale entries left behind by cheaper paths found later
+                if self.nodes[position] != NodeState::Visited {
+                    break Some(position);
+                }
+            },
+            Frontier::Walker(..) => None,
+        }
+    }
+
+    // Where moving from the tile ends up and what it costs, slides and all.
+    // Positions only, so doors stay shut unless the player already holds the key
+    // and spikes only hurt when the player is down to their last point of health.
+    fn move_from(
+        game_state: &GameState,
+        position: Position,
+        direction: Direction,
+    ) -> Option<(Position, usize)> {
+        let player = Player {
+            position,
+            direction,
+            inventory: game_state.player.inventory,
+            health: game_state.player.health,
+            reached: game_state.player.reached,
+            collected: game_state.player.collected,
+        };
+        let transition = game_state.transition(player, Action::from(direction));
+        (transition.player.position != position && !game_state.is_dead(&transition.player))
+            .then_some((transition.player.position, transition.cost))
+    }
+
+    pub fn step(&mut self, game_state: &GameState) -> SearchStatus {
+        if self.status != SearchStatus::Running {
+            return self.status;
+        }
+
+        if let Frontier::Walker(position, direction) = self.frontier {
+            self.walk(game_state, position, direction);
+            return self.status;
+        }
+
+        let Some(position) = self.pop() else {
+            self.status = SearchStatus::Exhausted;
+            return self.status;
+        };
+
+        self.nodes[position] = NodeState::Visited;
+        self.expansions += 1;
+
+        if self.goals.contains(&position) {
+            self.status = SearchStatus::Found;
+            self.path = self.trace_path(position);
+            return self.status;
+        }
+
+        let neighbours: Vec<(Position, usize)> = Direction::ALL
+            .into_iter()
+            .filter_map(|direction| Self::move_from(game_state, position, direction))
+            .collect();
+
+        for (neighbour, move_cost) in neighbours {
+            match &mut self.frontier {
+                Frontier::Queue(queue) => {
+                    if self.nodes[neighbour] == NodeState::Unseen {
+                        queue.push_back(neighbour);
+                        self.nodes[neighbour] = NodeState::Frontier;
+                        self.came_from[neighbour] = Some(position);
+                    }
+                }
+                Frontier::Stack(stack) => {
+                    if self.nodes[neighbour] == NodeState::Unseen {
+                        stack.push(neighbour);
+                        self.nodes[neighbour] = NodeState::Frontier;
+                        self.came_from[neighbour] = Some(position);
+                    }
+                }
+                Frontier::Heap(..) => {
+                    // Weighted by terrain, BFS and DFS only count tiles
+                    let cost = self.cost[position] + move_cost;
+                    if self.nodes[neighbour] != NodeState::Visited && cost < self.cost[neighbour] {
+                        self.cost[neighbour] = cost;
+                        self.came_from[neighbour] = Some(position);
+                        self.nodes[neighbour] = NodeState::Frontier;
+                        self.push_heap(neighbour, cost);
+                    }
+                }
+                Frontier::Walker(..) => unreachable!(),
+            }
+        }
+
+        self.status
+    }
+
+    // One move of the right-hand rule, turning as needed first
+    fn walk(&mut self, game_state: &GameState, position: Position, direction: Direction) {
+        let bit = 1 << direction as u8;
+        if self.walked[position] & bit != 0 {
+            // Same tile facing the same way again, it will only go in circles
+            self.status = SearchStatus::Exhausted;
+            return;
+        }
+        self.walked[position] |= bit;
+        self.nodes[position] = NodeState::Visited;
+        self.expansions += 1;
+
+        if self.goals.contains(&position) {
+            self.status = SearchStatus::Found;
+            self.path = self.trace_path(position);
+            return;
+        }
+
+        let candidates = [
+            direction.turn_right(),
+            direction,
+            direction.turn_left(),
+            direction.turn_right().turn_right(),
+        ];
+        let next = candidates.into_iter().find_map(|candidate| {
+            Self::move_from(game_state, position, candidate)
+                .map(|(neighbour, _)| (neighbour, candidate))
+        });
+
+        match next {
+            Some((neighbour, direction)) => {
+                if self.nodes[neighbour] == NodeState::Unseen {
+                    self.came_from[neighbour] = Some(position);
+                }
+                self.nodes[neighbour] = NodeState::Frontier;
+                self.frontier = Frontier::Walker(neighbour, direction);
+            }
+            None => self.status = SearchStatus::Exhausted,
+        }
+    }
+
+    fn trace_path(&self, goal: Position) -> Vec<Position> {
+        let mut path = vec![goal];
+        let mut position = goal;
+        while position != self.start {
+            match self.came_from[position] {
+                Some(previous) => {
+                    path.push(previous);
+                    position = previous;
+                }
+                None => break,
+            }
+        }
+        path.reverse();
+        path
+    }
+
+    pub fn run(&mut self, game_state: &GameState) -> SearchStatus {
+        while self.step(game_state) == SearchStatus::Running {}
+        self.status
+    }
+}
@@ -0,0 +1,544 @@
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap};
+
+use tracing::{debug, instrument};
+
+use crate::game_state::{GameState, Transition};
+use crate::types::{
+    Action, Direction, EndCondition, GoalMode, Inventory, KeyColor, Player, TileType,
+};
+
+// Every (position, direction) pair, times every combination of the keys lying
+// around and every health the player can have left when the maze has any.
+// Keys pair that with the goals reached or items collected so far.
+struct StateSpace {
+    tiles: usize,
+    // Only colors that can still be picked up vary, so the state space grows
+    // with the keys actually on the map
+    collectable: Vec<KeyColor>,
+    // Health only changes on spikes, mazes without them keep a single layer
+    health_levels: usize,
+}
+
+impl StateSpace {
+    fn new(game_state: &GameState) -> Self {
+        let mut collectable: Vec<KeyColor> = Vec::new();
+        let mut spikes = false;
+        for (_, tile) in game_state.tiles.iter() {
+            match tile.tile_type {
+                TileType::Key(color)
+                    if !game_state.player.inventory.has(color) && !collectable.contains(&color) =>
+                {
+                    collectable.push(color)
+                }
+                TileType::Spikes => spikes = true,
+                _ => {}
+            }
+        }
+
+        StateSpace {
+            tiles: game_state.tiles.len(),
+            collectable,
+            health_levels: if spikes {
+                game_state.player.health.max(1) as usize
+            } else {
+                1
+            },
+        }
+    }
+
+    // Only for players that are still alive, paired with their progress
+    fn key(&self, game_state: &GameState, player: &Player) -> (u32, usize) {
+        let layer = self
+            .collectable
+            .iter()
+            .enumerate()
+            .filter(|&(_, &color)| player.inventory.has(color))
+            .map(|(bit, _)| 1 << bit)
+            .sum::<usize>();
+        let health = if self.health_levels > 1 {
+            player.health as usize - 1
+        } else {
+            0
+        };
+        let layer = health * (1 << self.collectable.len()) + layer;
+        let index = (layer * self.tiles + game_state.tiles.index_of(player.position)) * 4
+            + player.direction as usize;
+        (progress(game_state, player), index)
+    }
+
+    // Every inventory the player can hold, starting from what they have
+    fn inventories(&self, start: Inventory) -> Vec<Inventory> {
+        (0..1 << self.collectable.len())
+            .map(|layer: usize| {
+                let mut inventory = start;
+                for (bit, &color) in self.collectable.iter().enumerate() {
+                    if layer & (1 << bit) != 0 {
+                        inventory.insert(color);
+                    }
+                }
+                inventory
+            })
+            .collect()
+    }
+}
+
+// The goals reached or the items collected, whichever decide the game
+fn progress(game_state: &GameState, player: &Player) -> u32 {
+    match game_state.end_condition {
+        EndCondition::Goal => player.reached,
+        EndCondition::CollectAll => player.collected,
+    }
+}
+
+// Where each action leads and what it costs, leaving out actions that change
+// nothing or kill the player
+fn successors(
+    game_state: &GameState,
+    player: Player,
+) -> impl Iterator<Item = (Action, Transition)> + '_ {
+    Action::ALL.into_iter().filter_map(move |action| {
+        let transition = game_state.transition(player, action);
+        let next = transition.player;
+        (next != player && !game_state.is_dead(&next)).then_some((action, transition))
+    })
+}
+
+fn pair_index(game_state: &GameState, player: &Player) -> usize {
+    game_state.tiles.index_of(player.position) * 4 + player.direction as usize
+}
+
+// Lower bounds on what winning takes from any state, for A*. They come from a
+// relaxed maze where the player holds every key they could pick up and has
+// health to spare, measured over (position, direction) pairs so turns count.
+// Visiting several goals or items combines the distances Held-Karp style, the
+// cheapest tour through the ones left over their pairwise distances.
+struct Estimates {
+    mode: GoalMode,
+    // Cheapest way from each (position, direction) pair to reaching each of
+    // the goals or items that decide the game
+    to_target: Vec<Vec<u32>>,
+    // Cheapest way from having just reached one target to having reached all
+    // the others in a set, by set and then by the one just reached
+    tours: Vec<Vec<u32>>,
+    // What the checkpoints after each one take on their own, in order
+    tails: Vec<u32>,
+}
+
+impl Estimates {
+    fn new(
+        game_state: &GameState,
+        states: &StateSpace,
+        step_cost: &impl Fn(&Transition) -> usize,
+    ) -> Self {
+        let (targets, mode) = match game_state.end_condition {
+            EndCondition::Goal => (game_state.goal_count(), game_state.goal_mode),
+            EndCondition::CollectAll => (game_state.items.len(), GoalMode::All),
+        };
+
+        // Edges of the relaxed maze as (to, from, cost), and the ones that
+        // reach targets as (from, to, cost, targets reached)
+        let pairs = game_state.tiles.len() * 4;
+        let inventories = states.inventories(game_state.player.inventory);
+        let all_keys = *inventories.last().unwrap();
+        let mut edges: Vec<(u32, u32, u32)> = Vec::new();
+        let mut arrivals: Vec<(u32, u32, u32, u32)> = Vec::new();
+        let mut outgoing = Vec::new();
+        for (position, tile) in game_state.tiles.iter() {
+            if tile.tile_type.blocks(all_keys) || tile.tile_type == TileType::Pit {
+                continue;
+            }
+            for direction in Direction::ALL {
+                // Keys only change where a move ends up when it runs into doors
+                outgoing.clear();
+                for &inventory in &inventories {
+                    let player = Player {
+                        position,
+                        direction,
+                        inventory,
+                        health: u32::MAX,
+                        reached: 0,
+                        collected: 0,
+                    };
+                    for (_, transition) in successors(game_state, player) {
+                        let next = transition.player;
+                        let edge = (next, step_cost(&transition) as u32);
+                        if (next.position != position || next.direction != direction)
+                            && !outgoing.contains(&edge)
+                        {
+                            outgoing.push(edge);
+                        }
+                    }
+                }
+
+                let from = game_state.tiles.index_of(position) * 4 + direction as usize;
+                for &(next, cost) in &outgoing {
+                    let to = pair_index(game_state, &next);
+                    edges.push((to as u32, from as u32, cost));
+                    let reached = match game_state.end_condition {
+                        EndCondition::Goal => game_state
+                            .goals()
+                            .position(|goal| goal == next.position)
+                            .map_or(0, |index| 1 << index),
+                        EndCondition::CollectAll => next.collected,
+                    };
+                    if reached != 0 {
+                        arrivals.push((from as u32, to as u32, cost, reached));
+                    }
+                }
+            }
+        }
+
+        // Distances run backwards, so each pair lists the pairs that lead
+        // there with what it costs, grouped by a counting sort
+        let mut incoming = vec![0; pairs + 1];
+        for &(to, _, _) in &edges {
+            incoming[to as usize + 1] += 1;
+        }
+        for pair in 0..pairs {
+            incoming[pair + 1] += incoming[pair];
+        }
+        let mut sources = vec![(0, 0); edges.len()];
+        let mut filled = incoming.clone();
+        for &(to, from, cost) in &edges {
+            sources[filled[to as usize]] = (from, cost);
+            filled[to as usize] += 1;
+        }
+        let max_cost = edges.iter().map(|&(_, _, cost)| cost).max().unwrap_or(0);
+        drop(edges);
+
+        // Dijkstra with a ring of buckets, one per cost it can still reach,
+        // since every step costs a few actions at most
+        let to_target: Vec<Vec<u32>> = (0..targets)
+            .map(|target| {
+                let mut distance = vec![u32::MAX; pairs];
+                let ring = max_cost as usize + 1;
+                let mut buckets = vec![Vec::new(); ring];
+                let mut queued = 0;
+                for &(from, _, cost, reached) in &arrivals {
+                    if reached & (1 << target) != 0 && cost < distance[from as usize] {
+                        distance[from as usize] = cost;
+                        buckets[cost as usize % ring].push(from);
+                        queued += 1;
+                    }
+                }
+                let mut cost = 0;
+                while queued > 0 {
+                    let bucket = std::mem::take(&mut buckets[cost as usize % ring]);
+                    queued -= bucket.len();
+                    for &pair in &bucket {
+                        if distance[pair as usize] != cost {
+                            continue;
+                        }
+                        let pair = pair as usize;
+                        for &(from, step) in &sources[incoming[pair]..incoming[pair + 1]] {
+                            let next_cost = cost + step;
+                            if next_cost < distance[from as usize] {
+                                distance[from as usize] = next_cost;
+                                buckets[next_cost as usize % ring].push(from);
+                                queued += 1;
+                            }
+                        }
+                    }
+                    buckets[cost as usize % ring] = bucket;
+                    buckets[cost as usize % ring].clear();
+                    cost += 1;
+                }
+                distance
+            })
+            .collect();
+
+        // Reaching two targets with one action costs nothing in between
+        let between: Vec<Vec<u32>> = (0..targets)
+            .map(|first| {
+                (0..targets)
+                    .map(|second| {
+                        arrivals
+                            .iter()
+                            .filter(|&&(_, _, _, reached)| reached & (1 << first) != 0)
+                            .map(|&(_, to, _, reached)| {
+                                if reached & (1 << second) != 0 {
+                                    0
+                                } else {
+                                    to_target[second][to as usize]
+                                }
+                            })
+                            .min()
+                            .unwrap_or(u32::MAX)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut tours = vec![vec![u32::MAX; targets]; 1 << targets];
+        for set in 1..1usize << targets {
+            for last in (0..targets).filter(|&last| set & (1 << last) != 0) {
+                let rest = set & !(1 << last);
+                tours[set][last] = if rest == 0 {
+                    0
+                } else {
+                    (0..targets)
+                        .filter(|&next| rest & (1 << next) != 0)
+                        .map(|next| between[last][next].saturating_add(tours[rest][next]))
+                        .min()
+                        .unwrap()
+                };
+            }
+        }
+
+        let mut tails = vec![0; targets];
+        for target in (0..targets.saturating_sub(1)).rev() {
+            tails[target] = between[target][target + 1].saturating_add(tails[target + 1]);
+        }
+
+        Estimates {
+            mode,
+            to_target,
+            tours,
+            tails,
+        }
+    }
+
+    // None when some target is out of reach even in the relaxed maze
+    fn estimate(&self, game_state: &GameState, player: &Player) -> Option<usize> {
+        let pair = pair_index(game_state, player);
+        let progress = progress(game_state, player);
+        let estimate = match self.mode {
+            GoalMode::Ordered => {
+                let next = progress.count_ones() as usize;
+                if next >= self.to_target.len() {
+                    0
+                } else {
+                    self.to_target[next][pair].saturating_add(self.tails[next])
+                }
+            }
+            GoalMode::Any => self
+                .to_target
+                .iter()
+                .map(|distance| distance[pair])
+                .min()
+                .unwrap_or(0),
+            GoalMode::All => {
+                let left = !progress as usize & (self.tours.len() - 1);
+                (0..self.to_target.len())
+                    .filter(|&target| left & (1 << target) != 0)
+                    .map(|target| {
+                        self.to_target[target][pair].saturating_add(self.tours[left][target])
+                    })
+                    .min()
+                    .unwrap_or(0)
+            }
+        };
+        (estimate != u32::MAX).then_some(estimate as usize)
+    }
+}
+
+struct Visit {
+    cost: usize,
+    previous: Option<((u32, usize), Action)>,
+    done: bool,
+}
+
+// Cheapest action sequence to winning by `step_cost`. A* with `Estimates`,
+// which never overestimate, so the first winning state out of the heap is the
+// best one. States are only stored once the search reaches them, the goals and
+// items visited make for too many combinations to lay them all out.
+fn plan(
+    game_state: &GameState,
+    step_cost: impl Fn(&Transition) -> usize,
+) -> Option<(Vec<Action>, usize)> {
+    let start = game_state.player;
+    if game_state.is_dead(&start) {
+        return None;
+    }
+    let states = StateSpace::new(game_state);
+    let estimates = Estimates::new(game_state, &states, &step_cost);
+    let estimate = |player: &Player| estimates.estimate(game_state, player);
+
+    let mut visits: HashMap<(u32, usize), Visit> = HashMap::new();
+    // Ordered by (estimate, deepest first, insertion order), the order also
+    // indexes the pushed states
+    let mut heap = BinaryHeap::new();
+    let mut pushed = vec![start];
+
+    visits.insert(
+        states.key(game_state, &start),
+        Visit {
+            cost: 0,
+            previous: None,
+            done: false,
+        },
+    );
+    heap.push(Reverse((estimate(&start)?, Reverse(0), 0)));
+
+    while let Some(Reverse((_, Reverse(cost), order))) = heap.pop() {
+        let state = pushed[order];
+        let current = states.key(game_state, &state);
+        let visit = visits.get_mut(&current).unwrap();
+        if visit.done || cost > visit.cost {
+            continue;
+        }
+        visit.done = true;
+
+        if game_state.is_won(&state) {
+            debug!(states = visits.len(), cost, "plan found");
+            let mut actions = Vec::new();
+            let mut node = current;
+            while let Some((parent, action)) = visits[&node].previous {
+                actions.push(action);
+                node = parent;
+            }
+            actions.reverse();
+            return Some((actions, cost));
+        }
+
+        for (action, transition) in successors(game_state, state) {
+            let next = transition.player;
+            let Some(remaining) = estimate(&next) else {
+                continue;
+            };
+            let next_cost = cost + step_cost(&transition);
+            let improved = match visits.entry(states.key(game_state, &next)) {
+                Entry::Occupied(mut entry) => {
+                    let visit = entry.get_mut();
+                    let improved = !visit.done && next_cost < visit.cost;
+                    if improved {
+                        visit.cost = next_cost;
+                        visit.previous = Some((current, action));
+                    }
+                    improved
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(Visit {
+                        cost: next_cost,
+                        previous: Some((current, action)),
+                        done: false,
+                    });
+                    true
+                }
+            };
+            if improved {
+                heap.push(Reverse((
+                    next_cost + remaining,
+                    Reverse(next_cost),
+                    pushed.len(),
+                )));
+                pushed.push(next);
+            }
+        }
+    }
+
+    debug!(states = visits.len(), "no plan");
+    None
+}
+
+// Shortest action sequence from the player to the goal, or through every goal
+// or item in the best order when all of them count. Turning and moving both
+// cost one action, so the search runs over (position, direction) pairs. Slides
+// and conveyor pushes come for free with the action that caused them.
+#[instrument(level = "debug", skip_all, fields(width = game_state.width, height = game_state.height))]
+pub fn optimal_actions(game_state: &GameState) -> Option<Vec<Action>> {
+    plan(game_state, |_| 1).map(|(actions, _)| actions)
+}
+
+// Cheapest action sequence to the goal by terrain cost, which can take more
+// actions than `optimal_actions` to go around mud and water
+#[instrument(level = "debug", skip_all, fields(width = game_state.width, height = game_state.height))]
+pub fn cheapest_actions(game_state: &GameState) -> Option<(Vec<Action>, usize)> {
+    plan(game_state, |transition| transition.cost)
+}
+
+pub fn is_solvable(game_state: &GameState) -> bool {
+    optimal_actions(game_state).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_state::TURN_COST;
+    use crate::types::{Outcome, Terrain};
+
+    // One corridor facing right with a goal three tiles back and another two
+    // ahead. Turning costs an action, so each goal mode has its own answer.
+    const CORRIDOR: &str = "①..>.②\n";
+
+    fn solve(map: &str) -> (GameState, Vec<Action>) {
+        let game_state = GameState::from_map_str(map).unwrap();
+        let actions = optimal_actions(&game_state).unwrap();
+        (game_state, actions)
+    }
+
+    // Plays the actions and returns the goals reached after each one
+    fn play(game_state: &mut GameState, actions: &[Action]) -> Vec<u32> {
+        actions
+            .iter()
+            .map(|&action| {
+                game_state.apply_action(action);
+                game_state.player.reached
+            })
+            .collect()
+    }
+
+    #[test]
+    fn any_goal_takes_the_nearest() {
+        let (mut game_state, actions) = solve(CORRIDOR);
+
+        assert_eq!(actions.len(), 2);
+        play(&mut game_state, &actions);
+        assert_eq!(game_state.outcome, Outcome::Success);
+    }
+
+    #[test]
+    fn all_goals_take_the_shortest_tour() {
+        let (mut game_state, actions) = solve(&format!("goal_mode: all\n{CORRIDOR}"));
+
+        // Ahead first, then turn and walk the whole corridor back
+        assert_eq!(actions.len(), 2 + 1 + 5);
+        play(&mut game_state, &actions);
+        assert_eq!(game_state.outcome, Outcome::Success);
+    }
+
+    #[test]
+    fn ordered_checkpoints_are_visited_in_order() {
+        let (mut game_state, actions) = solve(&format!("goal_mode: ordered\n{CORRIDOR}"));
+
+        // Back to the first checkpoint, then turn and walk past the start
+        assert_eq!(actions.len(), 1 + 3 + 1 + 5);
+        let mut reached = play(&mut game_state, &actions);
+        reached.dedup();
+        assert_eq!(reached, [0, 0b01, 0b11]);
+        assert_eq!(game_state.outcome, Outcome::Success);
+    }
+
+    #[test]
+    fn collect_all_picks_up_every_item() {
+        let map = "end_condition: collect_all\n$..>.%\n#####G\n";
+        let (mut game_state, actions) = solve(map);
+
+        assert_eq!(actions.len(), 2 + 1 + 5);
+        play(&mut game_state, &actions);
+        assert_eq!(game_state.outcome, Outcome::Success);
+        assert_eq!(game_state.player.collected, 0b11);
+    }
+
+    #[test]
+    fn cheapest_plan_weighs_terrain() {
+        // Straight across the water takes fewer actions, round it costs less
+        let game_state = GameState::from_map_str(">~~G\n....\n").unwrap();
+        let (actions, cost) = cheapest_actions(&game_state).unwrap();
+
+        assert_eq!(optimal_actions(&game_state).unwrap().len(), 3);
+        // Three turns and five plain steps
+        assert_eq!(actions.len(), 3 + 5);
+        assert_eq!(cost, 3 * TURN_COST + 5 * Terrain::Plain.cost());
+    }
+
+    #[test]
+    fn walled_off_goal_is_unsolvable() {
+        let game_state = GameState::from_map_str(">.#G\n").unwrap();
+
+        assert!(!is_solvable(&game_state));
+        assert_eq!(cheapest_actions(&game_state), None);
+    }
+}
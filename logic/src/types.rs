@@ -1,13 +1,164 @@
+use std::fmt;
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TileType {
     Wall,
     Walkable,
     Goal,
+    // Picked up by walking over it, stays on the map
+    Key(KeyColor),
+    // Blocks movement and sight unless the player holds the matching key
+    Door(KeyColor),
+    // Stepping on one moves the player to the other tile with the same id
+    Teleporter(u8),
+    // Keeps the player sliding the way they were going
+    Ice,
+    // Pushes the player one tile at the end of every step
+    Conveyor(Direction),
+    // Falling in ends the episode
+    Pit,
+    // Costs a point of health every time the player steps on them
+    Spikes,
+}
+
+impl TileType {
+    pub fn blocks(&self, inventory: Inventory) -> bool {
+        match self {
+            TileType::Wall => true,
+            TileType::Door(color) => !inventory.has(*color),
+            _ => false,
+        }
+    }
+
+    pub fn is_hazard(&self) -> bool {
+        matches!(self, TileType::Pit | TileType::Spikes)
+    }
+
+    // Sight stops at doors even when the player could open them
+    pub fn is_opaque(&self) -> bool {
+        matches!(self, TileType::Wall | TileType::Door(_))
+    }
+}
+
+// Ground under walkable tiles, entering a tile costs its terrain's cost
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Terrain {
+    Road,
+    #[default]
+    Plain,
+    Mud,
+    Water,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 4] = [Terrain::Road, Terrain::Plain, Terrain::Mud, Terrain::Water];
+    // Lower bound on the cost of a move, for heuristics
+    pub const MIN_COST: usize = 1;
+
+    pub fn cost(&self) -> usize {
+        match self {
+            Terrain::Road => 1,
+            Terrain::Plain => 2,
+            Terrain::Mud => 4,
+            Terrain::Water => 8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Terrain::Road => "road",
+            Terrain::Plain => "plain",
+            Terrain::Mud => "mud",
+            Terrain::Water => "water",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyColor {
+    Red,
+    Blue,
+    Yellow,
+    Purple,
+}
+
+impl KeyColor {
+    pub const ALL: [KeyColor; 4] = [
+        KeyColor::Red,
+        KeyColor::Blue,
+        KeyColor::Yellow,
+        KeyColor::Purple,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyColor::Red => "red",
+            KeyColor::Blue => "blue",
+            KeyColor::Yellow => "yellow",
+            KeyColor::Purple => "purple",
+        }
+    }
+}
+
+// Lies on a walkable tile until the player steps on it, adding to the score
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Item {
+    Coin,
+    Gem,
+}
+
+impl Item {
+    pub const ALL: [Item; 2] = [Item::Coin, Item::Gem];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Item::Coin => "coin",
+            Item::Gem => "gem",
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        match self {
+            Item::Coin => 1,
+            Item::Gem => 5,
+        }
+    }
+}
+
+// Keys held, one bit per color so the player stays Copy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Inventory(u8);
+
+impl Inventory {
+    pub fn has(&self, color: KeyColor) -> bool {
+        self.0 & (1 << color as u8) != 0
+    }
+
+    pub fn insert(&mut self, color: KeyColor) {
+        self.0 |= 1 << color as u8;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = KeyColor> + use<> {
+        let bits = self.0;
+        KeyColor::ALL
+            .into_iter()
+            .filter(move |&color| bits & (1 << color as u8) != 0)
+    }
+
+    // Dense index for solvers, below 2^KeyColor::ALL.len()
+    pub fn bits(&self) -> usize {
+        self.0 as usize
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct Tile {
     pub tile_type: TileType,
+    pub terrain: Terrain,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -16,6 +167,21 @@ pub struct Position {
     pub y: usize,
 }
 
+impl Position {
+    // Returns the adjacent position in the given direction, if it is inside the map
+    pub fn neighbour(&self, direction: Direction, width: usize, height: usize) -> Option<Position> {
+        let (dx, dy) = direction.delta();
+        let x = self.x.checked_add_signed(dx)?;
+        let y = self.y.checked_add_signed(dy)?;
+
+        if x < width && y < height {
+            Some(Position { x, y })
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Direction {
     Up,
@@ -24,13 +190,55 @@ pub enum Direction {
     Left,
 }
 
-#[derive(Debug)]
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Right,
+        Direction::Left,
+    ];
+
+    pub fn delta(&self) -> (isize, isize) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        }
+    }
+
+    pub fn turn_right(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    pub fn turn_left(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Player {
     pub position: Position,
     pub direction: Direction,
+    pub inventory: Inventory,
+    pub health: u32,
+    // Goals reached so far, one bit per goal in `GameState::goals` order
+    pub reached: u32,
+    // Items picked up so far, one bit per entry in `GameState::items`
+    pub collected: u32,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Action {
     Up,
     Down,
@@ -38,8 +246,126 @@ pub enum Action {
     Left,
 }
 
+impl Action {
+    pub const ALL: [Action; 4] = [Action::Up, Action::Down, Action::Right, Action::Left];
+}
+
+impl From<Direction> for Action {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Up => Action::Up,
+            Direction::Down => Action::Down,
+            Direction::Right => Action::Right,
+            Direction::Left => Action::Left,
+        }
+    }
+}
+
+impl From<Action> for Direction {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Up => Direction::Up,
+            Action::Down => Direction::Down,
+            Action::Right => Direction::Right,
+            Action::Left => Direction::Left,
+        }
+    }
+}
+
+// What it takes to win when a maze has more than one goal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GoalMode {
+    // Any one of them
+    #[default]
+    Any,
+    // Every one of them, in any order
+    All,
+    // Every one of them, as checkpoints in the order they are listed
+    Ordered,
+}
+
+impl GoalMode {
+    pub const ALL: [GoalMode; 3] = [GoalMode::Any, GoalMode::All, GoalMode::Ordered];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GoalMode::Any => "any",
+            GoalMode::All => "all",
+            GoalMode::Ordered => "ordered",
+        }
+    }
+}
+
+impl fmt::Display for GoalMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            GoalMode::Any => "Reach any goal",
+            GoalMode::All => "Visit every goal",
+            GoalMode::Ordered => "Checkpoints in order",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// What ends an episode in success
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EndCondition {
+    // The goals, as the goal mode says, with items only adding to the score
+    #[default]
+    Goal,
+    // Every item, the goals are just tiles
+    CollectAll,
+}
+
+impl EndCondition {
+    pub const ALL: [EndCondition; 2] = [EndCondition::Goal, EndCondition::CollectAll];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EndCondition::Goal => "goal",
+            EndCondition::CollectAll => "collect_all",
+        }
+    }
+}
+
+impl fmt::Display for EndCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            EndCondition::Goal => "Reach the goal",
+            EndCondition::CollectAll => "Collect every item",
+        };
+        write!(f, "{name}")
+    }
+}
+
+// How an episode stands, anything but InProgress means it has ended
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    InProgress,
+    Success,
+    // Fell into a pit or ran out of health
+    Died,
+    TimedOut,
+}
+
 #[derive(Debug, Clone)]
 pub struct Observation {
+    pub position: Position,
     pub direction: Direction,
     pub visible_tiles: Vec<TileType>,
+    // Matches visible_tiles, plain where the ray left the maze
+    pub visible_terrain: Vec<Terrain>,
+    // Indices into visible_tiles where an enemy stands
+    pub visible_enemies: Vec<usize>,
+    // Items still lying on visible tiles, by index into visible_tiles
+    pub visible_items: Vec<(usize, Item)>,
+    // What the player is standing on
+    pub terrain: Terrain,
+    pub inventory: Inventory,
+    pub health: u32,
+    // Goals that count towards winning so far, out of how many there are to reach
+    pub goals_reached: usize,
+    pub goals_needed: usize,
+    pub score: u32,
+    pub items_left: usize,
 }
@@ -0,0 +1,123 @@
+use std::io::Result;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use logic::{Action, Direction, GameState, Outcome};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::map_view::{MapView, key_color};
+
+#[derive(Debug)]
+pub struct App {
+    game_state: GameState,
+    // Why the last reset failed, until the next one works
+    error: Option<String>,
+    exit: bool,
+}
+
+impl App {
+    pub fn new(width: usize, height: usize) -> Self {
+        App {
+            game_state: GameState::new(width, height),
+            error: None,
+            exit: false,
+        }
+    }
+
+    pub fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        while !self.exit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key_event) = event::read()? {
+                self.handle_key(key_event);
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let [map_area, status_area] =
+            Layout::vertical([Constraint::Min(3), Constraint::Length(2)]).areas(frame.area());
+
+        let map_block = Block::bordered().title(" Maze ");
+        let map_inner = map_block.inner(map_area);
+        frame.render_widget(map_block, map_area);
+        frame.render_widget(MapView::new(&self.game_state), map_inner);
+
+        frame.render_widget(Paragraph::new(self.status_lines()), status_area);
+    }
+
+    fn status_lines(&self) -> Vec<Line<'_>> {
+        let player = &self.game_state.player;
+        let direction = match player.direction {
+            Direction::Up => "Up",
+            Direction::Down => "Down",
+            Direction::Left => "Left",
+            Direction::Right => "Right",
+        };
+
+        let mut status = vec![Span::raw(format!(
+            "Facing {} at ({}, {}) on {} | cost {} | health {} | goals {}/{} | score {} | view {} tiles",
+            direction,
+            player.position.x,
+            player.position.y,
+            self.game_state.tiles[player.position].terrain.name(),
+            self.game_state.cost,
+            player.health,
+            player.reached.count_ones(),
+            self.game_state.goals_needed(),
+            self.game_state.score(),
+            self.game_state.get_player_view().len(),
+        ))];
+        for color in player.inventory.keys() {
+            status.push(Span::styled(" ⚷", Style::default().fg(key_color(color))));
+        }
+        match self.game_state.outcome {
+            Outcome::InProgress => {}
+            Outcome::Success => status.push(Span::styled(
+                format!("  Goal reached in {} steps!", self.game_state.steps),
+                Style::default().fg(Color::Green).bold(),
+            )),
+            Outcome::Died => status.push(Span::styled(
+                "  You died",
+                Style::default().fg(Color::Red).bold(),
+            )),
+            Outcome::TimedOut => status.push(Span::styled(
+                "  Out of steps",
+                Style::default().fg(Color::Red).bold(),
+            )),
+        }
+        if let Some(error) = &self.error {
+            status.push(Span::styled(
+                format!("  {error}"),
+                Style::default().fg(Color::Red),
+            ));
+        }
+
+        vec![
+            Line::from(status),
+            Line::from("←↑↓→ turn/move · r reset · q quit").dark_gray(),
+        ]
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) {
+        // Some terminals also report releases, which would double every move
+        if key_event.kind != KeyEventKind::Press {
+            return;
+        }
+
+        match key_event.code {
+            KeyCode::Up => self.game_state.apply_action(Action::Up),
+            KeyCode::Down => self.game_state.apply_action(Action::Down),
+            KeyCode::Left => self.game_state.apply_action(Action::Left),
+            KeyCode::Right => self.game_state.apply_action(Action::Right),
+            KeyCode::Char('r') => {
+                self.error = self.game_state.reset().err().map(|error| error.to_string());
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.exit = true,
+            _ => {}
+        }
+    }
+}
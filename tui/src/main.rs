@@ -0,0 +1,35 @@
+mod app;
+mod map_view;
+
+use std::fs::File;
+use std::io::Result;
+use std::sync::Mutex;
+
+use app::App;
+use tracing_subscriber::EnvFilter;
+
+const DEFAULT_WIDTH: usize = 50;
+const DEFAULT_HEIGHT: usize = 30;
+const LOG_FILE: &str = "tui.log";
+
+// The terminal belongs to the UI, so logs go to a file and only when RUST_LOG is set
+fn init_logging() -> Result<()> {
+    let Ok(filter) = EnvFilter::try_from_default_env() else {
+        return Ok(());
+    };
+
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_ansi(false)
+        .with_writer(Mutex::new(File::create(LOG_FILE)?))
+        .init();
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    init_logging()?;
+    let mut terminal = ratatui::init();
+    let result = App::new(DEFAULT_WIDTH, DEFAULT_HEIGHT).run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,247 @@
+use logic::{Direction, EnemyBehavior, GameState, Item, KeyColor, Position, Terrain, TileType};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::Widget;
+
+// Terminal cells are roughly twice as tall as they are wide
+const CELLS_PER_TILE: u16 = 2;
+
+const MUD: Color = Color::Rgb(139, 90, 43);
+
+const TELEPORTER_SYMBOLS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+const TELEPORTER_COLORS: [Color; 5] = [
+    Color::Cyan,
+    Color::LightMagenta,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+];
+
+pub struct MapView<'a> {
+    game_state: &'a GameState,
+}
+
+impl<'a> MapView<'a> {
+    pub fn new(game_state: &'a GameState) -> Self {
+        MapView { game_state }
+    }
+
+    fn is_reached(&self, position: Position) -> bool {
+        self.game_state
+            .goals()
+            .position(|goal| goal == position)
+            .is_some_and(|index| self.game_state.player.reached & (1 << index) != 0)
+    }
+
+    fn item_left_at(&self, position: Position) -> Option<Item> {
+        self.game_state
+            .item_at(position)
+            .filter(|index| self.game_state.player.collected & (1 << index) == 0)
+            .map(|index| self.game_state.items[index].1)
+    }
+
+    fn is_wall(&self, x: isize, y: isize) -> bool {
+        if x < 0 || y < 0 {
+            return false;
+        }
+        let position = Position {
+            x: x as usize,
+            y: y as usize,
+        };
+        self.game_state
+            .tiles
+            .get(position)
+            .is_some_and(|tile| tile.tile_type == TileType::Wall)
+    }
+
+    // Picks the box-drawing glyph that joins a wall to its wall neighbours
+    fn wall_glyph(&self, x: usize, y: usize) -> &'static str {
+        let (x, y) = (x as isize, y as isize);
+        let up = self.is_wall(x, y - 1);
+        let down = self.is_wall(x, y + 1);
+        let left = self.is_wall(x - 1, y);
+        let right = self.is_wall(x + 1, y);
+
+        match (up, down, left, right) {
+            (false, false, false, false) => "■",
+            (_, _, false, false) => "│",
+            (false, false, _, _) => "─",
+            (false, true, false, true) => "┌",
+            (false, true, true, false) => "┐",
+            (true, false, false, true) => "└",
+            (true, false, true, false) => "┘",
+            (true, true, false, true) => "├",
+            (true, true, true, false) => "┤",
+            (false, true, true, true) => "┬",
+            (true, false, true, true) => "┴",
+            (true, true, true, true) => "┼",
+        }
+    }
+
+    // Scrolls so the player stays visible when the maze is larger than the area
+    fn origin(&self, area: Rect) -> Position {
+        let visible_width = (area.width / CELLS_PER_TILE) as usize;
+        let visible_height = area.height as usize;
+        let player = self.game_state.player.position;
+
+        let scroll = |player: usize, visible: usize, size: usize| {
+            player
+                .saturating_sub(visible / 2)
+                .min(size.saturating_sub(visible))
+        };
+
+        Position {
+            x: scroll(player.x, visible_width, self.game_state.width),
+            y: scroll(player.y, visible_height, self.game_state.height),
+        }
+    }
+}
+
+impl Widget for MapView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let origin = self.origin(area);
+        let view_positions = self.game_state.get_player_view();
+        let player = &self.game_state.player;
+
+        for row in 0..area.height {
+            for column in 0..area.width / CELLS_PER_TILE {
+                let x = origin.x + column as usize;
+                let y = origin.y + row as usize;
+                if x >= self.game_state.width || y >= self.game_state.height {
+                    continue;
+                }
+
+                let position = Position { x, y };
+                let enemy = self
+                    .game_state
+                    .enemies
+                    .iter()
+                    .find(|enemy| enemy.position == position);
+                let (symbol, fill, style) = if position == player.position {
+                    let arrow = match player.direction {
+                        Direction::Up => "▲",
+                        Direction::Down => "▼",
+                        Direction::Left => "◀",
+                        Direction::Right => "▶",
+                    };
+                    (
+                        arrow,
+                        " ",
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    )
+                } else if let Some(enemy) = enemy {
+                    let color = match enemy.behavior {
+                        EnemyBehavior::Patrol { .. } => Color::LightRed,
+                        EnemyBehavior::RandomWalk => Color::Magenta,
+                        EnemyBehavior::Chaser => Color::Red,
+                    };
+                    (
+                        "☻",
+                        " ",
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    )
+                } else if let Some(item) = self.item_left_at(position) {
+                    let (symbol, color) = match item {
+                        Item::Coin => ("$", Color::Yellow),
+                        Item::Gem => ("♦", Color::Cyan),
+                    };
+                    (
+                        symbol,
+                        " ",
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
+                    )
+                } else {
+                    match self.game_state.tiles[position].tile_type {
+                        TileType::Wall => {
+                            let fill = if self.is_wall(x as isize + 1, y as isize) {
+                                "─"
+                            } else {
+                                " "
+                            };
+                            (
+                                self.wall_glyph(x, y),
+                                fill,
+                                Style::default().fg(Color::Gray),
+                            )
+                        }
+                        // Reached goals stay on the map, hollowed out
+                        TileType::Goal if self.is_reached(position) => {
+                            ("◇", " ", Style::default().fg(Color::Green))
+                        }
+                        TileType::Goal => (
+                            "◆",
+                            " ",
+                            Style::default()
+                                .fg(Color::Green)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        TileType::Walkable => match self.game_state.tiles[position].terrain {
+                            Terrain::Plain => (" ", " ", Style::default()),
+                            Terrain::Road => ("═", "═", Style::default().fg(Color::DarkGray)),
+                            Terrain::Mud => ("░", "░", Style::default().fg(MUD)),
+                            Terrain::Water => ("≈", "≈", Style::default().fg(Color::Blue)),
+                        },
+                        TileType::Key(color) => (
+                            "⚷",
+                            " ",
+                            Style::default()
+                                .fg(key_color(color))
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                        // Doors the player can open are drawn hollow
+                        TileType::Door(color) if player.inventory.has(color) => {
+                            ("▒", "▒", Style::default().fg(key_color(color)))
+                        }
+                        TileType::Door(color) => ("█", "█", Style::default().fg(key_color(color))),
+                        TileType::Pit => ("○", " ", Style::default().fg(Color::DarkGray)),
+                        TileType::Spikes => ("✕", " ", Style::default().fg(Color::LightRed)),
+                        TileType::Ice => ("·", "·", Style::default().fg(Color::LightCyan)),
+                        TileType::Conveyor(direction) => {
+                            let arrow = match direction {
+                                Direction::Up => "↑",
+                                Direction::Down => "↓",
+                                Direction::Left => "←",
+                                Direction::Right => "→",
+                            };
+                            (arrow, " ", Style::default().fg(Color::DarkGray))
+                        }
+                        // Both ends of a pair share their digit and color
+                        TileType::Teleporter(id) => (
+                            TELEPORTER_SYMBOLS[id as usize % TELEPORTER_SYMBOLS.len()],
+                            " ",
+                            Style::default()
+                                .fg(TELEPORTER_COLORS[id as usize % TELEPORTER_COLORS.len()])
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    }
+                };
+
+                // Tint the view ray like the iced overlay does
+                let style = if view_positions.contains(&position) {
+                    style.bg(Color::Yellow)
+                } else {
+                    style
+                };
+
+                let cell_x = area.x + column * CELLS_PER_TILE;
+                let cell_y = area.y + row;
+                if let Some(cell) = buf.cell_mut((cell_x, cell_y)) {
+                    cell.set_symbol(symbol).set_style(style);
+                }
+                if let Some(cell) = buf.cell_mut((cell_x + 1, cell_y)) {
+                    cell.set_symbol(fill).set_style(style);
+                }
+            }
+        }
+    }
+}
+
+pub fn key_color(color: KeyColor) -> Color {
+    match color {
+        KeyColor::Red => Color::Red,
+        KeyColor::Blue => Color::Blue,
+        KeyColor::Yellow => Color::Yellow,
+        KeyColor::Purple => Color::Magenta,
+    }
+}
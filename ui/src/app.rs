@@ -1,67 +1,474 @@
-use iced::mouse::Cursor;
-use iced::widget::canvas::{self, Canvas, Cache};
-use iced::widget::{column, container, row};
-use iced::{Element, Event, Rectangle, Renderer, Subscription, Theme};
-use iced::keyboard::{Event as KeyboardEvent, Key, key};
-use logic::{Action, GameState};
+use std::collections::VecDeque;
+use std::fs;
 
+use iced::keyboard::Event as KeyboardEvent;
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{self, Cache, Canvas};
+use iced::widget::{center, column, container, row, stack};
+use iced::{Element, Event, Fill, Rectangle, Renderer, Subscription, Task, Theme};
+use tracing::warn;
+
+use logic::{Action, ConfigError, GameState, Player, Snapshot, is_solvable, parse_map};
+
+use crate::background;
+
+use crate::camera::{Camera, CameraState};
+use crate::controller_view::{Controller, ControllerChoice, ControllerView};
 use crate::controls_view::{ControlsView, Message};
+use crate::editor_view::{Editor, EditorState, EditorView};
+use crate::enemy_view::EnemyView;
+use crate::heatmap_view::HeatmapView;
+use crate::keybindings::{Command, Keybindings};
 use crate::map_view::MapView;
 use crate::player_view::PlayerView;
+use crate::settings_view::{Settings, SettingsView};
+use crate::solver_view::{Solver, SolverView};
+use crate::trail_view::{Trail, TrailView};
+use crate::victory_view::{Episode, VictoryView};
 
-const SQUARE_SIZE: u32 = 20;
 const DEFAULT_WIDTH: usize = 50;
 const DEFAULT_HEIGHT: usize = 30;
+// Zoomed all the way out every tile is redrawn, which gets sluggish past this
+const MAX_MAZE_SIZE: usize = 1024;
+const MAX_UNDO: usize = 10_000;
 
 #[derive(Debug)]
 pub struct App {
     game_state: GameState,
     tiles_cache: Cache,
+    editor: Editor,
+    settings: Settings,
+    camera: Camera,
+    solver: Solver,
+    controller: Controller,
+    show_heatmap: bool,
+    trail: Trail,
+    episode: Episode,
+    keybindings: Keybindings,
+    history: VecDeque<Snapshot>,
+}
+
+#[derive(Debug, Default)]
+pub struct CanvasState {
+    editor: EditorState,
+    camera: CameraState,
 }
 
 impl App {
-    pub fn new(width: usize, height: usize) -> Self {
-        App {
-            game_state: GameState::new(width, height),
+    pub fn new(width: usize, height: usize) -> Result<Self, ConfigError> {
+        let game_state = GameState::builder()
+            .size(width, height)
+            .max_size(Self::max_tiles(), Self::max_tiles())
+            .build()?;
+
+        Ok(App {
+            editor: Editor::default(),
+            settings: Settings::new(&game_state),
+            camera: Camera::new(&game_state),
+            solver: Solver::default(),
+            controller: Controller::default(),
+            show_heatmap: false,
+            trail: Trail::new(&game_state),
+            episode: Episode::default(),
+            keybindings: Keybindings::load(),
+            history: VecDeque::new(),
+            game_state,
             tiles_cache: Cache::default(),
-        }
+        })
     }
 
-    pub fn view(&self) -> Element<'_, Message> {
-        let canvas_width = (self.game_state.width as u32 * SQUARE_SIZE) as f32;
-        let canvas_height = (self.game_state.height as u32 * SQUARE_SIZE) as f32;
+    // Starts the first solvability check along with the window
+    pub fn boot() -> (Self, Task<Message>) {
+        let mut app = App::default();
+        let task = app.check_solvable();
+        (app, task)
+    }
 
+    fn max_tiles() -> usize {
+        MAX_MAZE_SIZE
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
         container(
-            column![
-                Canvas::new(self).width(canvas_width).height(canvas_height),
-                row![ControlsView::view()],
+            row![
+                column![
+                    stack![Canvas::new(self).width(Fill).height(Fill)]
+                        .push(VictoryView::view(&self.game_state, &self.episode).map(center)),
+                    row![
+                        column![
+                            ControlsView::view(
+                                self.camera.follow_player,
+                                self.show_heatmap,
+                                &self.game_state,
+                            ),
+                            TrailView::view(&self.trail),
+                        ]
+                        .spacing(10),
+                        ControllerView::view(&self.controller),
+                        EditorView::view(&self.editor)
+                    ]
+                    .spacing(20),
+                ],
+                column![
+                    SettingsView::view(&self.settings, &self.game_state),
+                    SolverView::view(&self.solver),
+                ]
+                .spacing(20),
             ]
+            .spacing(20),
         )
         .into()
     }
 
-    pub fn update(&mut self, message: Message) {
+    pub fn update(&mut self, message: Message) -> Task<Message> {
         match message {
-            Message::Reset => {
-                self.game_state.reset();
+            Message::Reset => match self.game_state.reset() {
+                Ok(()) => return self.game_replaced(),
+                Err(error) => {
+                    warn!(%error, "failed to generate a new maze");
+                    self.settings.error = Some(error.to_string());
+                }
+            },
+            Message::PlayAgain => {
+                self.game_state.restart();
+                return self.episode_restarted();
+            }
+            Message::Undo => self.undo(),
+            Message::KeyPressed(key, modifiers) => {
+                if let Some(command) = self.keybindings.command(&key, modifiers) {
+                    return self.update(self.command_message(command));
+                }
+            }
+            Message::Up => return self.apply_action(Action::Up),
+            Message::Down => return self.apply_action(Action::Down),
+            Message::Right => return self.apply_action(Action::Right),
+            Message::Left => return self.apply_action(Action::Left),
+            Message::ToggleEditing(editing) => self.editor.editing = editing,
+            Message::PaintTile(position, tile_type) => {
+                if self.game_state.set_tile(position, tile_type) {
+                    return self.map_edited();
+                }
+            }
+            Message::PlaceGoal(position) => {
+                if self.game_state.set_goal(position) {
+                    return self.map_edited();
+                }
+            }
+            Message::PlacePlayer(position) => {
+                if self.game_state.set_player(position) {
+                    return self.episode_restarted();
+                }
+            }
+            Message::MapPathChanged(map_path) => self.editor.map_path = map_path,
+            Message::SaveMap => {
+                self.editor.status = Some(match self.game_state.save_map(&self.editor.map_path) {
+                    Ok(()) => format!("Saved to {}", self.editor.map_path),
+                    Err(error) => {
+                        warn!(path = %self.editor.map_path, %error, "failed to save map");
+                        error.to_string()
+                    }
+                });
+            }
+            Message::LoadMap => match self.load_map() {
+                Ok(game_state) => {
+                    self.game_state = game_state;
+                    self.editor.status = Some(format!("Loaded {}", self.editor.map_path));
+                    return self.game_replaced();
+                }
+                Err(error) => {
+                    warn!(path = %self.editor.map_path, %error, "failed to load map");
+                    self.editor.status = Some(error.to_string());
+                }
+            },
+            Message::WidthChanged(width) => self.settings.width = width,
+            Message::HeightChanged(height) => self.settings.height = height,
+            Message::GeneratorSelected(generator) => self.settings.generator = generator,
+            Message::WallDensityChanged(wall_density) => self.settings.wall_density = wall_density,
+            Message::SeedChanged(seed) => self.settings.seed = seed,
+            Message::ViewRangeChanged(view_range) => self.settings.view_range = view_range,
+            Message::StepLimitChanged(step_limit) => self.settings.step_limit = step_limit,
+            Message::DoorsChanged(doors) => self.settings.doors = doors,
+            Message::TeleportersChanged(teleporters) => self.settings.teleporters = teleporters,
+            Message::TerrainToggled(terrain) => self.settings.terrain = terrain,
+            Message::SlipperyChanged(slippery) => self.settings.slippery = slippery,
+            Message::HazardsChanged(hazards) => self.settings.hazards = hazards,
+            Message::HazardsVisibleToggled(visible) => self.settings.hazards_visible = visible,
+            Message::HealthChanged(health) => self.settings.health = health,
+            Message::EnemiesChanged(enemies) => self.settings.enemies = enemies,
+            Message::DynamicWallsChanged(walls) => self.settings.dynamic_walls = walls,
+            Message::GoalsChanged(goals) => self.settings.goals = goals,
+            Message::GoalModeSelected(goal_mode) => self.settings.goal_mode = goal_mode,
+            Message::ItemsChanged(items) => self.settings.items = items,
+            Message::EndConditionSelected(end_condition) => {
+                self.settings.end_condition = end_condition
+            }
+            Message::Regenerate => match self.regenerate() {
+                Ok(game_state) => {
+                    self.game_state = game_state;
+                    self.settings.error = None;
+                    return self.game_replaced();
+                }
+                Err(error) => self.settings.error = Some(error),
+            },
+            Message::CameraChanged(camera) => {
+                self.camera = camera;
+                self.tiles_cache.clear();
+            }
+            Message::FollowPlayer(follow_player) => {
+                self.camera.follow_player = follow_player;
                 self.tiles_cache.clear();
             }
-            Message::Up => self.game_state.apply_action(Action::Up),
-            Message::Down => self.game_state.apply_action(Action::Down),
-            Message::Right => self.game_state.apply_action(Action::Right),
-            Message::Left => self.game_state.apply_action(Action::Left),
+            Message::FitToWindow => {
+                self.camera = Camera {
+                    follow_player: self.camera.follow_player,
+                    ..Camera::new(&self.game_state)
+                };
+                self.tiles_cache.clear();
+            }
+            Message::ShowHeatmap(show_heatmap) => self.show_heatmap = show_heatmap,
+            Message::ShowTrail(enabled) => self.trail.enabled = enabled,
+            Message::FadeTrail(fading) => self.trail.fading = fading,
+            Message::TrailLengthChanged(length) => self.trail.set_length(length),
+            Message::SolverSelected(algorithm) => {
+                self.solver.algorithm = algorithm;
+                self.solver.clear();
+            }
+            Message::SolverPlay => self.solver.play(&self.game_state),
+            Message::SolverPause => self.solver.running = false,
+            Message::SolverStep => self.solver.step(&self.game_state),
+            Message::SolverClear => self.solver.clear(),
+            Message::SolverSpeedChanged(speed) => self.solver.speed = speed,
+            Message::SolverTick => self.solver.tick(&self.game_state),
+            Message::ControllerSelected(choice) => self.controller.select(choice, &self.game_state),
+            Message::ControllerPlay => self.controller.running = true,
+            Message::ControllerPause => self.controller.running = false,
+            Message::ControllerStep => {
+                self.controller.running = false;
+                return self.agent_act(1);
+            }
+            Message::ControllerSpeedChanged(speed) => self.controller.speed = speed,
+            Message::ControllerTick => return self.agent_act(self.controller.actions_per_tick()),
+            Message::SolvabilityChecked(edits, solvable) => {
+                self.editor.checking = false;
+                // Edits made while checking need another look
+                if edits != self.editor.edits {
+                    return self.check_solvable();
+                }
+                self.editor.solvable = Some(solvable);
+            }
+            Message::BaselineFound(run, optimal_steps, cheapest_cost) => self
+                .episode
+                .baseline_found(run, optimal_steps, cheapest_cost),
+        }
+
+        Task::none()
+    }
+
+    fn command_message(&self, command: Command) -> Message {
+        match command {
+            Command::Up => Message::Up,
+            Command::Down => Message::Down,
+            Command::Left => Message::Left,
+            Command::Right => Message::Right,
+            Command::Reset => Message::Reset,
+            Command::Undo => Message::Undo,
+            // Pauses whatever is animating, the agent first
+            Command::Pause if self.controller.choice != ControllerChoice::Human => {
+                if self.controller.running {
+                    Message::ControllerPause
+                } else {
+                    Message::ControllerPlay
+                }
+            }
+            Command::Pause if self.solver.running => Message::SolverPause,
+            Command::Pause => Message::SolverPlay,
+            Command::ToggleSolver if self.solver.search.is_some() => Message::SolverClear,
+            Command::ToggleSolver => Message::SolverPlay,
+        }
+    }
+
+    fn undo(&mut self) {
+        let Some(snapshot) = self.history.pop_back() else {
+            return;
+        };
+
+        let inventory = self.game_state.player.inventory;
+        let reached = self.game_state.player.reached;
+        let collected = self.game_state.player.collected;
+        let revision = self.game_state.revision;
+        self.game_state.undo(snapshot);
+        self.trail.undo(&self.game_state);
+        self.episode.after_undo(&self.game_state);
+        if self.camera.follow_player
+            || self.game_state.player.inventory != inventory
+            || self.game_state.player.reached != reached
+            || self.game_state.player.collected != collected
+            || self.game_state.revision != revision
+        {
+            self.tiles_cache.clear();
+        }
+    }
+
+    fn apply_action(&mut self, action: Action) -> Task<Message> {
+        if !self.game_state.is_over() {
+            if self.history.len() == MAX_UNDO {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.game_state.snapshot());
+        }
+
+        let Player {
+            position,
+            direction,
+            inventory,
+            reached,
+            collected,
+            ..
+        } = self.game_state.player;
+        let revision = self.game_state.revision;
+        let baseline = self.episode.before_action(&self.game_state);
+        self.game_state.apply_action(action);
+        self.episode.after_action(&self.game_state);
+        self.trail.record(&self.game_state, position, direction);
+
+        // The cached tiles are drawn around the player, doors show whether they
+        // can be opened, reached goals fade, picked up items vanish and dynamic
+        // walls move on their own
+        if self.camera.follow_player
+            || self.game_state.player.inventory != inventory
+            || self.game_state.player.reached != reached
+            || self.game_state.player.collected != collected
+            || self.game_state.revision != revision
+        {
+            self.tiles_cache.clear();
+        }
+
+        baseline
+    }
+
+    fn agent_act(&mut self, actions: usize) -> Task<Message> {
+        let mut tasks = Vec::new();
+        for _ in 0..actions {
+            if self.game_state.is_over() {
+                self.controller.running = false;
+                break;
+            }
+            let Some(action) = self.controller.act(&self.game_state) else {
+                break;
+            };
+            tasks.push(self.apply_action(action));
         }
+        Task::batch(tasks)
+    }
+
+    fn regenerate(&self) -> Result<GameState, String> {
+        self.settings
+            .builder()?
+            .max_size(Self::max_tiles(), Self::max_tiles())
+            .build()
+            .map_err(|error| error.to_string())
+    }
+
+    fn load_map(&self) -> Result<GameState, ConfigError> {
+        let map = fs::read_to_string(&self.editor.map_path)
+            .map_err(|error| ConfigError::Io(format!("{}: {error}", self.editor.map_path)))?;
+        parse_map(&map)?
+            .max_size(Self::max_tiles(), Self::max_tiles())
+            .build()
+    }
+
+    fn game_replaced(&mut self) -> Task<Message> {
+        self.camera = Camera {
+            follow_player: self.camera.follow_player,
+            ..Camera::new(&self.game_state)
+        };
+        self.episode_restarted()
+    }
+
+    fn episode_restarted(&mut self) -> Task<Message> {
+        self.trail.restart(&self.game_state);
+        self.episode.restart();
+        self.history.clear();
+        self.map_edited()
+    }
+
+    fn map_edited(&mut self) -> Task<Message> {
+        self.tiles_cache.clear();
+        // A search over the old layout would show stale results
+        self.solver.clear();
+        self.controller.reset(&self.game_state);
+        self.editor.solvable = None;
+        self.editor.edits += 1;
+        self.check_solvable()
+    }
+
+    // One check at a time, painting a stroke only needs the last layout checked
+    fn check_solvable(&mut self) -> Task<Message> {
+        if self.editor.checking {
+            return Task::none();
+        }
+        self.editor.checking = true;
+
+        let edits = self.editor.edits;
+        let game_state = self.game_state.clone();
+        background::search(move || is_solvable(&game_state))
+            .map(move |solvable| Message::SolvabilityChecked(edits, solvable))
     }
 }
 
 impl Default for App {
     fn default() -> Self {
-        App::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+        App::new(DEFAULT_WIDTH, DEFAULT_HEIGHT).expect("default maze size is valid")
     }
 }
 
 impl canvas::Program<Message> for App {
-    type State = ();
+    type State = CanvasState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: &canvas::Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        let camera_action = self.camera.update(
+            &self.game_state,
+            &mut state.camera,
+            event,
+            bounds,
+            cursor,
+            !self.editor.editing,
+        );
+        if camera_action.is_some() || !self.editor.editing {
+            return camera_action;
+        }
+
+        let viewport = self.camera.viewport(&self.game_state, bounds.size());
+        EditorView::update(
+            &self.game_state,
+            &mut state.editor,
+            event,
+            bounds,
+            cursor,
+            &viewport,
+        )
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &Self::State,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        if state.camera.is_panning() {
+            mouse::Interaction::Grabbing
+        } else if self.editor.editing && cursor.is_over(bounds) {
+            mouse::Interaction::Crosshair
+        } else {
+            mouse::Interaction::default()
+        }
+    }
 
     fn draw(
         &self,
@@ -71,41 +478,65 @@ impl canvas::Program<Message> for App {
         bounds: Rectangle,
         _cursor: Cursor,
     ) -> Vec<canvas::Geometry> {
+        let viewport = self.camera.viewport(&self.game_state, bounds.size());
+
         let tiles_geometry = MapView::draw(
             &self.game_state,
             &self.tiles_cache,
             renderer,
             bounds.size(),
+            &viewport,
         );
 
-        let player_geometry = PlayerView::draw(
-            &self.game_state,
-            renderer,
-            bounds.size(),
-        );
+        let trail_geometry = self
+            .trail
+            .enabled
+            .then(|| TrailView::draw(&self.trail, renderer, bounds.size(), &viewport));
+
+        let player_geometry =
+            PlayerView::draw(&self.game_state, renderer, bounds.size(), &viewport);
+
+        let enemy_geometry = (!self.game_state.enemies.is_empty())
+            .then(|| EnemyView::draw(&self.game_state, renderer, bounds.size(), &viewport));
 
-        vec![tiles_geometry, player_geometry]
+        let heatmap_geometry = self
+            .show_heatmap
+            .then(|| HeatmapView::draw(&self.game_state, renderer, bounds.size(), &viewport));
+
+        let solver_geometry = SolverView::draw(&self.solver, renderer, bounds.size(), &viewport);
+
+        [
+            Some(tiles_geometry),
+            heatmap_geometry,
+            solver_geometry,
+            trail_geometry,
+            Some(player_geometry),
+            enemy_geometry,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
     }
 }
 
-pub fn subscription(_state: &App) -> Subscription<Message> {
-    iced::event::listen().filter_map(|event| match event {
-        Event::Keyboard(KeyboardEvent::KeyPressed {
-            key: Key::Named(key::Named::ArrowUp),
-            ..
-        }) => Some(Message::Up),
-        Event::Keyboard(KeyboardEvent::KeyPressed {
-            key: Key::Named(key::Named::ArrowDown),
-            ..
-        }) => Some(Message::Down),
-        Event::Keyboard(KeyboardEvent::KeyPressed {
-            key: Key::Named(key::Named::ArrowLeft),
-            ..
-        }) => Some(Message::Left),
-        Event::Keyboard(KeyboardEvent::KeyPressed {
-            key: Key::Named(key::Named::ArrowRight),
-            ..
-        }) => Some(Message::Right),
+pub fn subscription(state: &App) -> Subscription<Message> {
+    // Only keys no widget used, so typing in a text field doesn't move the player
+    let keys = iced::event::listen().filter_map(|event| match event {
+        Event::Keyboard(KeyboardEvent::KeyPressed { key, modifiers, .. }) => {
+            Some(Message::KeyPressed(key, modifiers))
+        }
         _ => None,
-    })
+    });
+
+    let mut subscriptions = vec![keys];
+    if state.solver.running {
+        subscriptions
+            .push(iced::time::every(state.solver.tick_interval()).map(|_| Message::SolverTick));
+    }
+    if state.controller.running {
+        subscriptions.push(
+            iced::time::every(state.controller.tick_interval()).map(|_| Message::ControllerTick),
+        );
+    }
+    Subscription::batch(subscriptions)
 }
@@ -0,0 +1,15 @@
+use std::thread;
+
+use iced::Task;
+use iced::futures::channel::oneshot;
+
+// Runs a search on its own thread so a big maze doesn't freeze the window.
+// The task yields the result once the search is done.
+pub fn search<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+    let (sender, receiver) = oneshot::channel();
+    thread::spawn(move || {
+        // Nobody is waiting any more if the window closed
+        let _ = sender.send(work());
+    });
+    Task::perform(receiver, Result::ok).and_then(Task::done)
+}
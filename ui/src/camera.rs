@@ -0,0 +1,208 @@
+use std::ops::Range;
+
+use iced::mouse::{self, Cursor, ScrollDelta};
+use iced::widget::canvas::{self, Event};
+use iced::{Point, Rectangle, Size, Vector};
+use logic::{GameState, Position};
+
+use crate::controls_view::Message;
+
+const MIN_ZOOM: f32 = 0.5;
+const MAX_ZOOM: f32 = 50.0;
+const ZOOM_PER_LINE: f32 = 1.1;
+const PIXELS_PER_LINE: f32 = 50.0;
+
+// Zoom is relative to the scale that fits the whole maze in the canvas, so
+// resizing the window rescales the tiles with it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub zoom: f32,
+    // Maze point shown at the middle of the canvas, in tiles
+    pub center: Point,
+    pub follow_player: bool,
+}
+
+impl Camera {
+    pub fn new(game_state: &GameState) -> Self {
+        Camera {
+            zoom: 1.0,
+            center: Point::new(
+                game_state.width as f32 / 2.0,
+                game_state.height as f32 / 2.0,
+            ),
+            follow_player: false,
+        }
+    }
+
+    pub fn viewport(&self, game_state: &GameState, bounds: Size) -> Viewport {
+        let fit =
+            (bounds.width / game_state.width as f32).min(bounds.height / game_state.height as f32);
+        let scale = fit * self.zoom;
+
+        let center = if self.follow_player {
+            let player = game_state.player.position;
+            Point::new(player.x as f32 + 0.5, player.y as f32 + 0.5)
+        } else {
+            self.center
+        };
+
+        Viewport {
+            scale,
+            origin: Vector::new(
+                bounds.width / 2.0 - center.x * scale,
+                bounds.height / 2.0 - center.y * scale,
+            ),
+            width: game_state.width,
+            height: game_state.height,
+            bounds,
+        }
+    }
+
+    fn zoomed(&self, viewport: &Viewport, factor: f32, anchor: Point) -> Camera {
+        let zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let scale = viewport.scale * zoom / self.zoom;
+
+        // Keep the maze point under the cursor where it is, unless the player is the anchor
+        let center = if self.follow_player {
+            self.center
+        } else {
+            let anchored = viewport.maze_point(anchor);
+            Point::new(
+                anchored.x - (anchor.x - viewport.bounds.width / 2.0) / scale,
+                anchored.y - (anchor.y - viewport.bounds.height / 2.0) / scale,
+            )
+        };
+
+        Camera {
+            zoom,
+            center,
+            ..*self
+        }
+    }
+
+    // Wheel zooms, right or middle drag pans. Left drag pans too unless the
+    // editor needs it.
+    pub fn update(
+        &self,
+        game_state: &GameState,
+        state: &mut CameraState,
+        event: &Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+        left_drag_pans: bool,
+    ) -> Option<canvas::Action<Message>> {
+        let viewport = self.viewport(game_state, bounds.size());
+
+        let camera = match event {
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let anchor = cursor.position_in(bounds)?;
+                let lines = match delta {
+                    ScrollDelta::Lines { y, .. } => *y,
+                    ScrollDelta::Pixels { y, .. } => *y / PIXELS_PER_LINE,
+                };
+                self.zoomed(&viewport, ZOOM_PER_LINE.powf(lines), anchor)
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(button)) => {
+                let pans = matches!(button, mouse::Button::Right | mouse::Button::Middle)
+                    || (left_drag_pans && *button == mouse::Button::Left);
+                if !pans {
+                    return None;
+                }
+                state.panning = Some(cursor.position_in(bounds)?);
+                return Some(canvas::Action::capture());
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let last = state.panning?;
+                let current = cursor.position_in(bounds)?;
+                state.panning = Some(current);
+
+                let center = viewport.maze_point(Point::new(
+                    viewport.bounds.width / 2.0 - (current.x - last.x),
+                    viewport.bounds.height / 2.0 - (current.y - last.y),
+                ));
+                // Dragging takes the camera off the player
+                Camera {
+                    center,
+                    follow_player: false,
+                    ..*self
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(_)) => {
+                state.panning.take()?;
+                return Some(canvas::Action::capture());
+            }
+            _ => return None,
+        };
+
+        Some(canvas::Action::publish(Message::CameraChanged(camera)).and_capture())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct CameraState {
+    panning: Option<Point>,
+}
+
+impl CameraState {
+    pub fn is_panning(&self) -> bool {
+        self.panning.is_some()
+    }
+}
+
+// Maps between maze tiles and canvas pixels for one frame
+#[derive(Debug, Clone, Copy)]
+pub struct Viewport {
+    pub scale: f32,
+    origin: Vector,
+    width: usize,
+    height: usize,
+    bounds: Size,
+}
+
+impl Viewport {
+    pub fn tile_origin(&self, x: usize, y: usize) -> Point {
+        Point::new(
+            self.origin.x + x as f32 * self.scale,
+            self.origin.y + y as f32 * self.scale,
+        )
+    }
+
+    pub fn tile_size(&self) -> Size {
+        Size::new(self.scale, self.scale)
+    }
+
+    fn maze_point(&self, point: Point) -> Point {
+        Point::new(
+            (point.x - self.origin.x) / self.scale,
+            (point.y - self.origin.y) / self.scale,
+        )
+    }
+
+    pub fn tile_at(&self, point: Point) -> Option<Position> {
+        let maze = self.maze_point(point);
+        if maze.x < 0.0 || maze.y < 0.0 {
+            return None;
+        }
+
+        let position = Position {
+            x: maze.x as usize,
+            y: maze.y as usize,
+        };
+        (position.x < self.width && position.y < self.height).then_some(position)
+    }
+
+    // Only tiles that intersect the canvas need drawing
+    pub fn visible_columns(&self) -> Range<usize> {
+        Self::visible(self.origin.x, self.scale, self.bounds.width, self.width)
+    }
+
+    pub fn visible_rows(&self) -> Range<usize> {
+        Self::visible(self.origin.y, self.scale, self.bounds.height, self.height)
+    }
+
+    fn visible(origin: f32, scale: f32, extent: f32, count: usize) -> Range<usize> {
+        let first = ((-origin / scale).floor().max(0.0) as usize).min(count);
+        let last = (((extent - origin) / scale).ceil().max(0.0) as usize).min(count);
+        first..last.max(first)
+    }
+}
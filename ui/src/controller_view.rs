@@ -0,0 +1,152 @@
+use std::fmt;
+use std::time::Duration;
+
+use iced::widget::{Column, button, column, pick_list, row, slider, text};
+use logic::{Action, Agent, BfsFollower, DStarAgent, GameState, RandomAgent, WallFollower};
+
+use crate::controls_view::Message;
+
+const DEFAULT_SPEED: f32 = 5.0;
+const MAX_SPEED: f32 = 200.0;
+const MIN_TICK: Duration = Duration::from_millis(16);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ControllerChoice {
+    Human,
+    Random,
+    WallFollower,
+    BfsFollower,
+    DStar,
+    DStarFullMap,
+}
+
+impl ControllerChoice {
+    const ALL: [ControllerChoice; 6] = [
+        ControllerChoice::Human,
+        ControllerChoice::Random,
+        ControllerChoice::WallFollower,
+        ControllerChoice::BfsFollower,
+        ControllerChoice::DStar,
+        ControllerChoice::DStarFullMap,
+    ];
+}
+
+impl fmt::Display for ControllerChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ControllerChoice::Human => write!(f, "Human"),
+            ControllerChoice::Random => write!(f, "Random agent"),
+            ControllerChoice::WallFollower => write!(f, "Wall follower"),
+            ControllerChoice::BfsFollower => write!(f, "BFS follower"),
+            ControllerChoice::DStar => write!(f, "D* Lite explorer"),
+            ControllerChoice::DStarFullMap => write!(f, "D* Lite (full map)"),
+        }
+    }
+}
+
+// Drives the player with an agent instead of the keyboard
+pub struct Controller {
+    pub choice: ControllerChoice,
+    agent: Option<Box<dyn Agent>>,
+    pub running: bool,
+    // Actions per second
+    pub speed: f32,
+}
+
+impl fmt::Debug for Controller {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Controller")
+            .field("choice", &self.choice)
+            .field("agent", &self.agent.as_ref().map(|agent| agent.name()))
+            .field("running", &self.running)
+            .field("speed", &self.speed)
+            .finish()
+    }
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Controller {
+            choice: ControllerChoice::Human,
+            agent: None,
+            running: false,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+impl Controller {
+    pub fn select(&mut self, choice: ControllerChoice, game_state: &GameState) {
+        self.choice = choice;
+        self.reset(game_state);
+    }
+
+    // Agents plan from the maze at the start of an episode, so any new maze or
+    // edit starts them over
+    pub fn reset(&mut self, game_state: &GameState) {
+        self.running = false;
+        self.agent = match self.choice {
+            ControllerChoice::Human => None,
+            ControllerChoice::Random => Some(Box::new(RandomAgent::new(game_state.seed))),
+            ControllerChoice::WallFollower => Some(Box::<WallFollower>::default()),
+            ControllerChoice::BfsFollower => Some(Box::<BfsFollower>::default()),
+            ControllerChoice::DStar => Some(Box::new(DStarAgent::new(false))),
+            ControllerChoice::DStarFullMap => Some(Box::new(DStarAgent::new(true))),
+        };
+        if let Some(agent) = &mut self.agent {
+            agent.reset(game_state);
+        }
+    }
+
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.speed).max(MIN_TICK)
+    }
+
+    pub fn actions_per_tick(&self) -> usize {
+        (self.speed * self.tick_interval().as_secs_f32())
+            .round()
+            .max(1.0) as usize
+    }
+
+    pub fn act(&mut self, game_state: &GameState) -> Option<Action> {
+        let agent = self.agent.as_mut()?;
+        agent.sync(game_state);
+        Some(agent.act(&game_state.get_observation()))
+    }
+}
+
+pub struct ControllerView;
+
+impl ControllerView {
+    pub fn view(controller: &Controller) -> Column<'_, Message> {
+        let mut panel = column![
+            text("Controller").size(20),
+            pick_list(
+                ControllerChoice::ALL,
+                Some(controller.choice),
+                Message::ControllerSelected
+            ),
+        ]
+        .spacing(5)
+        .width(200);
+
+        if controller.choice != ControllerChoice::Human {
+            let play_pause = if controller.running {
+                button("Pause").on_press(Message::ControllerPause)
+            } else {
+                button("Play").on_press(Message::ControllerPlay)
+            };
+
+            panel = panel
+                .push(row![play_pause, button("Step").on_press(Message::ControllerStep)].spacing(5))
+                .push(text(format!("Speed {:.0} actions/s", controller.speed)).size(12))
+                .push(slider(
+                    1.0..=MAX_SPEED,
+                    controller.speed,
+                    Message::ControllerSpeedChanged,
+                ));
+        }
+
+        panel
+    }
+}
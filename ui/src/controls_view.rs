@@ -1,12 +1,72 @@
-use iced::widget::{button, column, container, row, Column};
+use iced::keyboard::{Key, Modifiers};
+use iced::widget::{Column, button, checkbox, column, container, row, text};
+use logic::{EndCondition, GameState, GoalMode, Position, SearchAlgorithm, TileType};
 
-#[derive(Debug, Clone, Copy)]
+use crate::camera::Camera;
+use crate::controller_view::ControllerChoice;
+use crate::settings_view::GeneratorChoice;
+
+#[derive(Debug, Clone)]
 pub enum Message {
     Reset,
+    PlayAgain,
+    Undo,
+    KeyPressed(Key, Modifiers),
     Up,
     Down,
     Right,
     Left,
+    ToggleEditing(bool),
+    PaintTile(Position, TileType),
+    PlaceGoal(Position),
+    PlacePlayer(Position),
+    MapPathChanged(String),
+    SaveMap,
+    LoadMap,
+    WidthChanged(String),
+    HeightChanged(String),
+    GeneratorSelected(GeneratorChoice),
+    WallDensityChanged(f32),
+    SeedChanged(String),
+    ViewRangeChanged(String),
+    StepLimitChanged(String),
+    DoorsChanged(String),
+    TeleportersChanged(String),
+    TerrainToggled(bool),
+    SlipperyChanged(String),
+    HazardsChanged(String),
+    HazardsVisibleToggled(bool),
+    HealthChanged(String),
+    EnemiesChanged(String),
+    DynamicWallsChanged(String),
+    GoalsChanged(String),
+    GoalModeSelected(GoalMode),
+    ItemsChanged(String),
+    EndConditionSelected(EndCondition),
+    Regenerate,
+    CameraChanged(Camera),
+    FollowPlayer(bool),
+    FitToWindow,
+    ShowHeatmap(bool),
+    ShowTrail(bool),
+    FadeTrail(bool),
+    TrailLengthChanged(usize),
+    SolverSelected(SearchAlgorithm),
+    SolverPlay,
+    SolverPause,
+    SolverStep,
+    SolverClear,
+    SolverSpeedChanged(f32),
+    SolverTick,
+    ControllerSelected(ControllerChoice),
+    ControllerPlay,
+    ControllerPause,
+    ControllerStep,
+    ControllerSpeedChanged(f32),
+    ControllerTick,
+    // Results of background searches, tagged with what they were run for
+    SolvabilityChecked(u64, bool),
+    BaselineFound(u64, Option<usize>, Option<usize>),
 }
 
 const BUTTON_SIZE: u32 = 30;
@@ -14,9 +74,44 @@ const BUTTON_SIZE: u32 = 30;
 pub struct ControlsView;
 
 impl ControlsView {
-    pub fn view() -> Column<'static, Message> {
+    pub fn view(
+        follow_player: bool,
+        show_heatmap: bool,
+        game_state: &GameState,
+    ) -> Column<'static, Message> {
+        let player = game_state.player;
+        let keys: Vec<&str> = player.inventory.keys().map(|color| color.name()).collect();
+        let keys = if keys.is_empty() {
+            "none".to_string()
+        } else {
+            keys.join(", ")
+        };
+
         column![
-            button("Reset").on_press(Message::Reset),
+            row![
+                button("Reset").on_press(Message::Reset),
+                button("Undo").on_press(Message::Undo),
+            ]
+            .spacing(5),
+            checkbox(follow_player)
+                .label("Follow player")
+                .on_toggle(Message::FollowPlayer),
+            button("Fit to window").on_press(Message::FitToWindow),
+            text(format!("Keys: {keys}")),
+            text(format!("Health: {}", player.health)),
+            text(format!(
+                "Goals: {}/{}",
+                player.reached.count_ones(),
+                game_state.goals_needed()
+            )),
+            text(format!(
+                "Score: {} ({} items left)",
+                game_state.score(),
+                game_state.items_left(&player)
+            )),
+            checkbox(show_heatmap)
+                .label("Visit heatmap")
+                .on_toggle(Message::ShowHeatmap),
             column![
                 row![
                     container("").height(BUTTON_SIZE).width(BUTTON_SIZE),
@@ -0,0 +1,122 @@
+use iced::keyboard::{self, Modifiers};
+use iced::mouse::{self, Cursor};
+use iced::widget::canvas::{self, Event};
+use iced::widget::{Column, button, checkbox, column, row, text, text_input};
+use iced::{Color, Rectangle};
+use logic::{GameState, Position, TileType};
+
+use crate::camera::Viewport;
+use crate::controls_view::Message;
+
+const DEFAULT_MAP_PATH: &str = "maze.txt";
+
+#[derive(Debug)]
+pub struct Editor {
+    pub editing: bool,
+    pub map_path: String,
+    // None until the check for the latest edit comes back
+    pub solvable: Option<bool>,
+    // Counts edits, so a check of an older layout can be told apart
+    pub edits: u64,
+    pub checking: bool,
+    pub status: Option<String>,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Editor {
+            editing: false,
+            map_path: DEFAULT_MAP_PATH.to_string(),
+            solvable: None,
+            edits: 0,
+            checking: false,
+            status: None,
+        }
+    }
+}
+
+// Canvas state, tracks what a drag is painting so it doesn't flicker back and forth
+#[derive(Debug, Default)]
+pub struct EditorState {
+    modifiers: Modifiers,
+    painting: Option<(TileType, Position)>,
+}
+
+pub struct EditorView;
+
+impl EditorView {
+    pub fn view(editor: &Editor) -> Column<'_, Message> {
+        let solvability = match editor.solvable {
+            Some(true) => text("Solvable").color(Color::from_rgb(0.0, 0.6, 0.0)),
+            Some(false) => text("Not solvable").color(Color::from_rgb(0.8, 0.0, 0.0)),
+            None => text("Checking..."),
+        };
+
+        column![
+            checkbox(editor.editing)
+                .label("Edit mode")
+                .on_toggle(Message::ToggleEditing),
+            text("Click to toggle walls, shift-click for goal, ctrl-click for player").size(12),
+            row![
+                text_input("Map file", &editor.map_path)
+                    .on_input(Message::MapPathChanged)
+                    .width(200),
+                button("Save").on_press(Message::SaveMap),
+                button("Load").on_press(Message::LoadMap),
+            ]
+            .spacing(5),
+            solvability,
+        ]
+        .push(editor.status.as_deref().map(text))
+        .spacing(5)
+    }
+
+    pub fn update(
+        game_state: &GameState,
+        state: &mut EditorState,
+        event: &Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+        viewport: &Viewport,
+    ) -> Option<canvas::Action<Message>> {
+        if let Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            state.modifiers = *modifiers;
+            return None;
+        }
+
+        let position = cursor
+            .position_in(bounds)
+            .and_then(|point| viewport.tile_at(point));
+
+        let message = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let position = position?;
+                if state.modifiers.shift() {
+                    Message::PlaceGoal(position)
+                } else if state.modifiers.command() {
+                    Message::PlacePlayer(position)
+                } else {
+                    let tile_type = match game_state.tiles[position].tile_type {
+                        TileType::Wall => TileType::Walkable,
+                        _ => TileType::Wall,
+                    };
+                    state.painting = Some((tile_type, position));
+                    Message::PaintTile(position, tile_type)
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let (tile_type, last) = state.painting?;
+                let position = position.filter(|&position| position != last)?;
+                state.painting = Some((tile_type, position));
+                Message::PaintTile(position, tile_type)
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                state.painting = None;
+                return None;
+            }
+            _ => return None,
+        };
+
+        Some(canvas::Action::publish(message).and_capture())
+    }
+}
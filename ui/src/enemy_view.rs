@@ -0,0 +1,40 @@
+use iced::widget::canvas::{self, Cache, Path};
+use iced::{Color, Point, Renderer, Size};
+use logic::{EnemyBehavior, GameState};
+
+use crate::camera::Viewport;
+
+const ENEMY_RADIUS_RATIO: f32 = 0.4;
+
+fn behavior_color(behavior: &EnemyBehavior) -> Color {
+    match behavior {
+        EnemyBehavior::Patrol { .. } => Color::from_rgb(1.0, 0.5, 0.0),
+        EnemyBehavior::RandomWalk => Color::from_rgb(0.6, 0.2, 0.8),
+        EnemyBehavior::Chaser => Color::from_rgb(0.7, 0.0, 0.0),
+    }
+}
+
+pub struct EnemyView;
+
+impl EnemyView {
+    pub fn draw(
+        game_state: &GameState,
+        renderer: &Renderer,
+        bounds_size: Size,
+        viewport: &Viewport,
+    ) -> canvas::Geometry {
+        Cache::default().draw(renderer, bounds_size, |frame| {
+            for enemy in &game_state.enemies {
+                let origin = viewport.tile_origin(enemy.position.x, enemy.position.y);
+                let center = Point::new(
+                    origin.x + viewport.scale / 2.0,
+                    origin.y + viewport.scale / 2.0,
+                );
+                frame.fill(
+                    &Path::circle(center, viewport.scale * ENEMY_RADIUS_RATIO),
+                    behavior_color(&enemy.behavior),
+                );
+            }
+        })
+    }
+}
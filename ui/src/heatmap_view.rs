@@ -0,0 +1,39 @@
+use iced::widget::canvas::{self, Cache};
+use iced::{Color, Renderer, Size};
+use logic::{GameState, Position};
+
+use crate::camera::Viewport;
+
+const MAX_ALPHA: f32 = 0.8;
+
+pub struct HeatmapView;
+
+impl HeatmapView {
+    pub fn draw(
+        game_state: &GameState,
+        renderer: &Renderer,
+        bounds_size: Size,
+        viewport: &Viewport,
+    ) -> canvas::Geometry {
+        // Log scale, otherwise one tile the player keeps returning to washes out the rest
+        let max = (game_state.max_visits() as f32).ln_1p().max(f32::EPSILON);
+
+        Cache::default().draw(renderer, bounds_size, |frame| {
+            for y in viewport.visible_rows() {
+                for x in viewport.visible_columns() {
+                    let visits = game_state.visits[Position { x, y }];
+                    if visits == 0 {
+                        continue;
+                    }
+
+                    let heat = (visits as f32).ln_1p() / max;
+                    frame.fill_rectangle(
+                        viewport.tile_origin(x, y),
+                        viewport.tile_size(),
+                        Color::from_rgba(1.0, 1.0 - heat, 0.0, MAX_ALPHA * (0.25 + 0.75 * heat)),
+                    );
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,182 @@
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+
+use iced::keyboard::{Key, Modifiers, key};
+use tracing::{info, warn};
+
+const DEFAULT_PATH: &str = "keybindings.txt";
+const PATH_VARIABLE: &str = "MAZE_KEYBINDINGS";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Up,
+    Down,
+    Left,
+    Right,
+    Reset,
+    Pause,
+    Undo,
+    ToggleSolver,
+}
+
+impl Command {
+    const ALL: [Command; 8] = [
+        Command::Up,
+        Command::Down,
+        Command::Left,
+        Command::Right,
+        Command::Reset,
+        Command::Pause,
+        Command::Undo,
+        Command::ToggleSolver,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Up => "up",
+            Command::Down => "down",
+            Command::Left => "left",
+            Command::Right => "right",
+            Command::Reset => "reset",
+            Command::Pause => "pause",
+            Command::Undo => "undo",
+            Command::ToggleSolver => "solver",
+        }
+    }
+}
+
+// A key as written in the config file: a character or a named key, optionally
+// with a ctrl+ prefix
+#[derive(Debug, Clone, PartialEq)]
+struct Binding {
+    ctrl: bool,
+    key: String,
+    command: Command,
+}
+
+const DEFAULT_BINDINGS: &str = "\
+arrowup = up
+arrowdown = down
+arrowleft = left
+arrowright = right
+w = up
+s = down
+a = left
+d = right
+k = up
+j = down
+h = left
+l = right
+r = reset
+space = pause
+u = undo
+ctrl+z = undo
+v = solver
+";
+
+// One `key = command` per line, `#` starts a comment. A config file replaces
+// the defaults entirely, so it has to list every key it wants.
+#[derive(Debug, Clone)]
+pub struct Keybindings {
+    bindings: Vec<Binding>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Keybindings::parse(DEFAULT_BINDINGS).expect("default keybindings are valid")
+    }
+}
+
+impl Keybindings {
+    pub fn parse(config: &str) -> Result<Self, String> {
+        let mut bindings = Vec::new();
+
+        for (number, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, command) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = command`", number + 1))?;
+            let command = command.trim();
+            let command = Command::ALL
+                .into_iter()
+                .find(|candidate| candidate.name() == command)
+                .ok_or_else(|| format!("line {}: unknown command `{command}`", number + 1))?;
+
+            let key = key.trim().to_lowercase();
+            let (ctrl, key) = match key.strip_prefix("ctrl+") {
+                Some(key) => (true, key.to_string()),
+                None => (false, key),
+            };
+            if key.is_empty() {
+                return Err(format!("line {}: missing key", number + 1));
+            }
+
+            bindings.push(Binding { ctrl, key, command });
+        }
+
+        Ok(Keybindings { bindings })
+    }
+
+    // Falls back to the defaults when there is no config file or it doesn't parse
+    pub fn load() -> Self {
+        let path = env::var(PATH_VARIABLE).unwrap_or_else(|_| DEFAULT_PATH.to_string());
+
+        let config = match fs::read_to_string(&path) {
+            Ok(config) => config,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Self::default(),
+            Err(error) => {
+                warn!(%path, %error, "failed to read keybindings, using defaults");
+                return Self::default();
+            }
+        };
+
+        match Self::parse(&config) {
+            Ok(keybindings) => {
+                info!(%path, bindings = keybindings.bindings.len(), "loaded keybindings");
+                keybindings
+            }
+            Err(error) => {
+                warn!(%path, %error, "invalid keybindings, using defaults");
+                Self::default()
+            }
+        }
+    }
+
+    pub fn command(&self, key: &Key, modifiers: Modifiers) -> Option<Command> {
+        let name = match key.as_ref() {
+            Key::Character(character) => character.to_lowercase(),
+            Key::Named(named) => named_key(named)?.to_string(),
+            Key::Unidentified => return None,
+        };
+
+        self.bindings
+            .iter()
+            .find(|binding| binding.key == name && binding.ctrl == modifiers.command())
+            .map(|binding| binding.command)
+    }
+}
+
+fn named_key(named: key::Named) -> Option<&'static str> {
+    Some(match named {
+        key::Named::ArrowUp => "arrowup",
+        key::Named::ArrowDown => "arrowdown",
+        key::Named::ArrowLeft => "arrowleft",
+        key::Named::ArrowRight => "arrowright",
+        key::Named::Space => "space",
+        key::Named::Enter => "enter",
+        key::Named::Escape => "escape",
+        key::Named::Backspace => "backspace",
+        key::Named::Tab => "tab",
+        key::Named::Delete => "delete",
+        key::Named::Home => "home",
+        key::Named::End => "end",
+        key::Named::PageUp => "pageup",
+        key::Named::PageDown => "pagedown",
+        _ => return None,
+    })
+}
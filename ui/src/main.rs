@@ -1,21 +1,39 @@
+mod app;
+mod background;
+mod camera;
+mod controller_view;
+mod controls_view;
+mod editor_view;
+mod enemy_view;
+mod heatmap_view;
+mod keybindings;
 mod map_view;
 mod player_view;
-mod controls_view;
-mod app;
+mod settings_view;
+mod solver_view;
+mod trail_view;
+mod victory_view;
 
-use iced::{Result, Task, Element};
 use app::App;
 use controls_view::Message;
+use iced::{Element, Result, Task};
+use tracing_subscriber::EnvFilter;
 
 fn main() -> Result {
-    iced::application(App::default, update, view)
+    // Quiet unless RUST_LOG asks for more, e.g. RUST_LOG=logic=info,ui=debug
+    tracing_subscriber::fmt()
+        .with_env_filter(
+            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
+        )
+        .init();
+
+    iced::application(App::boot, update, view)
         .subscription(app::subscription)
         .run()
 }
 
 fn update(state: &mut App, message: Message) -> Task<Message> {
-    state.update(message);
-    Task::none()
+    state.update(message)
 }
 
 fn view(state: &App) -> Element<'_, Message> {
@@ -1,9 +1,70 @@
-use iced::widget::canvas::{self, Cache};
-use iced::{Color, Point, Renderer, Size};
-use logic::{GameState, TileType};
+use iced::alignment::Vertical;
+use iced::widget::canvas::{self, Cache, Path, Stroke};
+use iced::widget::text::Alignment;
+use iced::{Color, Pixels, Point, Renderer, Size};
+use logic::{Direction, GameState, GoalMode, Item, KeyColor, Position, Terrain, TileType};
 use std::time::Instant;
+use tracing::{debug, debug_span};
 
-const SQUARE_SIZE: u32 = 20;
+use crate::camera::Viewport;
+
+// Keys are drawn as a smaller square inside their tile
+const KEY_INSET_RATIO: f32 = 0.3;
+const ITEM_RADIUS_RATIO: f32 = 0.3;
+
+pub fn key_color(color: KeyColor) -> Color {
+    match color {
+        KeyColor::Red => Color::from_rgb(0.9, 0.1, 0.1),
+        KeyColor::Blue => Color::from_rgb(0.1, 0.3, 0.9),
+        KeyColor::Yellow => Color::from_rgb(0.9, 0.8, 0.0),
+        KeyColor::Purple => Color::from_rgb(0.6, 0.1, 0.8),
+    }
+}
+
+pub fn terrain_color(terrain: Terrain) -> Color {
+    match terrain {
+        Terrain::Road => Color::from_rgb(0.75, 0.75, 0.75),
+        Terrain::Plain => Color::WHITE,
+        Terrain::Mud => Color::from_rgb(0.55, 0.4, 0.2),
+        Terrain::Water => Color::from_rgb(0.4, 0.6, 1.0),
+    }
+}
+
+// Pairs get hues spread around the color wheel so neighbouring ids differ
+pub fn teleporter_color(id: u8) -> Color {
+    let hue = (id as f32 * 0.382).fract() * 6.0;
+    let x = 1.0 - (hue % 2.0 - 1.0).abs();
+    let (r, g, b) = match hue as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::from_rgb(0.2 + 0.7 * r, 0.2 + 0.7 * g, 0.2 + 0.7 * b)
+}
+
+// A triangle pointing the way the belt pushes
+fn conveyor_arrow(origin: Point, scale: f32, direction: Direction) -> Path {
+    let center = Point::new(origin.x + scale / 2.0, origin.y + scale / 2.0);
+    let (dx, dy) = direction.delta();
+    let (dx, dy) = (dx as f32, dy as f32);
+    let reach = scale * 0.3;
+
+    Path::new(|builder| {
+        builder.move_to(Point::new(center.x + dx * reach, center.y + dy * reach));
+        builder.line_to(Point::new(
+            center.x - dx * reach - dy * reach,
+            center.y - dy * reach + dx * reach,
+        ));
+        builder.line_to(Point::new(
+            center.x - dx * reach + dy * reach,
+            center.y - dy * reach - dx * reach,
+        ));
+        builder.close();
+    })
+}
 
 pub struct MapView;
 
@@ -13,32 +74,151 @@ impl MapView {
         tiles_cache: &Cache,
         renderer: &Renderer,
         bounds_size: Size,
+        viewport: &Viewport,
     ) -> canvas::Geometry {
         tiles_cache.draw(renderer, bounds_size, |frame| {
+            let _span = debug_span!("tiles_cache_rebuild").entered();
             let compute_start = Instant::now();
-            let square_size = SQUARE_SIZE as f32;
 
-            for y in 0..game_state.height {
-                for x in 0..game_state.width {
-                    let tile = &game_state.tiles[x][y];
-                    let pos_x = x as f32 * square_size;
-                    let pos_y = y as f32 * square_size;
+            for y in viewport.visible_rows() {
+                for x in viewport.visible_columns() {
+                    let tile = &game_state.tiles[Position { x, y }];
 
                     let color = match tile.tile_type {
                         TileType::Goal => Color::from_rgb(0.0, 1.0, 0.0),
-                        TileType::Walkable => Color::WHITE,
+                        TileType::Walkable => terrain_color(tile.terrain),
+                        TileType::Key(_) => Color::WHITE,
                         TileType::Wall => Color::BLACK,
+                        // Doors the player can open fade out
+                        TileType::Door(color) if game_state.player.inventory.has(color) => Color {
+                            a: 0.3,
+                            ..key_color(color)
+                        },
+                        TileType::Door(color) => key_color(color),
+                        TileType::Teleporter(id) => teleporter_color(id),
+                        TileType::Ice => Color::from_rgb(0.75, 0.95, 1.0),
+                        TileType::Conveyor(_) => Color::from_rgb(0.5, 0.5, 0.5),
+                        TileType::Pit => Color::from_rgb(0.2, 0.1, 0.05),
+                        TileType::Spikes => Color::from_rgb(1.0, 0.6, 0.6),
                     };
+                    let origin = viewport.tile_origin(x, y);
+                    frame.fill_rectangle(origin, viewport.tile_size(), color);
+
+                    if let TileType::Conveyor(direction) = tile.tile_type {
+                        frame.fill(
+                            &conveyor_arrow(origin, viewport.scale, direction),
+                            Color::from_rgb(0.25, 0.25, 0.25),
+                        );
+                    }
+
+                    if let TileType::Key(color) = tile.tile_type {
+                        let inset = viewport.scale * KEY_INSET_RATIO;
+                        let size = viewport.scale - 2.0 * inset;
+                        frame.fill_rectangle(
+                            Point::new(origin.x + inset, origin.y + inset),
+                            Size::new(size, size),
+                            key_color(color),
+                        );
+                    }
+                }
+            }
+
+            // Dynamic walls stand out from the fixed ones, open or closed
+            let (columns, rows) = (viewport.visible_columns(), viewport.visible_rows());
+            for wall in &game_state.dynamic_walls {
+                let Position { x, y } = wall.position;
+                if !columns.contains(&x) || !rows.contains(&y) {
+                    continue;
+                }
+                let color = if game_state.tiles[wall.position].tile_type == TileType::Wall {
+                    Color::from_rgb(0.3, 0.3, 0.45)
+                } else {
+                    Color::from_rgb(0.85, 0.85, 0.95)
+                };
+                frame.fill_rectangle(viewport.tile_origin(x, y), viewport.tile_size(), color);
+            }
+
+            // Goals already reached fade out, checkpoints show their place in line
+            let numbered = game_state.goal_mode == GoalMode::Ordered && game_state.goal_count() > 1;
+            for (index, goal) in game_state.goals().enumerate() {
+                if !columns.contains(&goal.x) || !rows.contains(&goal.y) {
+                    continue;
+                }
+                let origin = viewport.tile_origin(goal.x, goal.y);
+                if game_state.player.reached & (1 << index) != 0 {
                     frame.fill_rectangle(
-                        Point::new(pos_x, pos_y),
-                        Size::new(square_size, square_size),
-                        color,
+                        origin,
+                        viewport.tile_size(),
+                        Color::from_rgb(0.6, 0.85, 0.6),
                     );
                 }
+                if numbered {
+                    frame.fill_text(canvas::Text {
+                        content: (index + 1).to_string(),
+                        position: Point::new(
+                            origin.x + viewport.scale / 2.0,
+                            origin.y + viewport.scale / 2.0,
+                        ),
+                        size: Pixels(viewport.scale * 0.6),
+                        align_x: Alignment::Center,
+                        align_y: Vertical::Center,
+                        ..canvas::Text::default()
+                    });
+                }
+            }
+
+            // Coins are round, gems are diamonds
+            for (_, position, item) in game_state.remaining_items(&game_state.player) {
+                if !columns.contains(&position.x) || !rows.contains(&position.y) {
+                    continue;
+                }
+                let origin = viewport.tile_origin(position.x, position.y);
+                let center = Point::new(
+                    origin.x + viewport.scale / 2.0,
+                    origin.y + viewport.scale / 2.0,
+                );
+                let radius = viewport.scale * ITEM_RADIUS_RATIO;
+                let (shape, color) = match item {
+                    Item::Coin => (Path::circle(center, radius), Color::from_rgb(1.0, 0.8, 0.1)),
+                    Item::Gem => (
+                        Path::new(|builder| {
+                            builder.move_to(Point::new(center.x, center.y - radius));
+                            builder.line_to(Point::new(center.x + radius, center.y));
+                            builder.line_to(Point::new(center.x, center.y + radius));
+                            builder.line_to(Point::new(center.x - radius, center.y));
+                            builder.close();
+                        }),
+                        Color::from_rgb(0.2, 0.8, 0.9),
+                    ),
+                };
+                frame.fill(&shape, color);
             }
-            println!(
-                "Tiles cache rebuild | Compute: {:.3}ms",
-                compute_start.elapsed().as_secs_f64() * 1000.0
+
+            // A faint line between partners hints where each one leads
+            for (id, [first, second]) in game_state.teleporters.iter().enumerate() {
+                let center = |position: Position| {
+                    let origin = viewport.tile_origin(position.x, position.y);
+                    Point::new(
+                        origin.x + viewport.scale / 2.0,
+                        origin.y + viewport.scale / 2.0,
+                    )
+                };
+                frame.stroke(
+                    &Path::line(center(*first), center(*second)),
+                    Stroke::default()
+                        .with_color(Color {
+                            a: 0.4,
+                            ..teleporter_color(id as u8)
+                        })
+                        .with_width((viewport.scale * 0.1).max(1.0)),
+                );
+            }
+
+            debug!(
+                columns = viewport.visible_columns().len(),
+                rows = viewport.visible_rows().len(),
+                compute_ms = compute_start.elapsed().as_secs_f64() * 1000.0,
+                "tiles cache rebuilt"
             );
         })
     }
@@ -2,8 +2,10 @@ use iced::widget::canvas::{self, Cache};
 use iced::{Color, Point, Renderer, Size};
 use logic::{Direction, GameState};
 
-const SQUARE_SIZE: u32 = 20;
-const DIRECTION_INDICATOR_SIZE: u32 = 4;
+use crate::camera::Viewport;
+
+// Thickness of the direction indicator relative to a tile
+const DIRECTION_INDICATOR_RATIO: f32 = 0.2;
 
 pub struct PlayerView;
 
@@ -12,13 +14,14 @@ impl PlayerView {
         game_state: &GameState,
         renderer: &Renderer,
         bounds_size: Size,
+        viewport: &Viewport,
     ) -> canvas::Geometry {
         Cache::default().draw(renderer, bounds_size, |frame| {
-            let square_size = SQUARE_SIZE as f32;
-            let direction_size = DIRECTION_INDICATOR_SIZE as f32;
+            let square_size = viewport.scale;
+            let direction_size = square_size * DIRECTION_INDICATOR_RATIO;
 
-            let pos_x = game_state.player.position.x as f32 * square_size;
-            let pos_y = game_state.player.position.y as f32 * square_size;
+            let player = game_state.player.position;
+            let Point { x: pos_x, y: pos_y } = viewport.tile_origin(player.x, player.y);
 
             // Player background
             frame.fill_rectangle(
@@ -55,12 +58,9 @@ impl PlayerView {
             // Draw player view overlay
             let view_positions = game_state.get_player_view();
             for pos in view_positions {
-                let view_x = pos.x as f32 * square_size;
-                let view_y = pos.y as f32 * square_size;
-
                 frame.fill_rectangle(
-                    Point::new(view_x, view_y),
-                    Size::new(square_size, square_size),
+                    viewport.tile_origin(pos.x, pos.y),
+                    viewport.tile_size(),
                     Color::from_rgba(1.0, 1.0, 0.0, 0.3),
                 );
             }
@@ -0,0 +1,298 @@
+use std::fmt;
+
+use iced::Color;
+use iced::widget::{Column, button, checkbox, column, pick_list, slider, text, text_input};
+use logic::{EndCondition, GameState, GameStateBuilder, Generator, GoalMode};
+
+use crate::controls_view::Message;
+
+const INPUT_WIDTH: u32 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GeneratorChoice {
+    Random,
+    Backtracker,
+}
+
+impl GeneratorChoice {
+    const ALL: [GeneratorChoice; 2] = [GeneratorChoice::Random, GeneratorChoice::Backtracker];
+}
+
+impl fmt::Display for GeneratorChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorChoice::Random => write!(f, "Random walls"),
+            GeneratorChoice::Backtracker => write!(f, "Backtracker"),
+        }
+    }
+}
+
+// Inputs are kept as typed so half-finished numbers don't get rejected while editing
+#[derive(Debug)]
+pub struct Settings {
+    pub width: String,
+    pub height: String,
+    pub generator: GeneratorChoice,
+    pub wall_density: f32,
+    pub seed: String,
+    pub view_range: String,
+    pub step_limit: String,
+    pub doors: String,
+    pub teleporters: String,
+    pub terrain: bool,
+    pub slippery: String,
+    pub hazards: String,
+    pub hazards_visible: bool,
+    pub health: String,
+    pub enemies: String,
+    pub dynamic_walls: String,
+    pub goals: String,
+    pub goal_mode: GoalMode,
+    pub items: String,
+    pub end_condition: EndCondition,
+    pub error: Option<String>,
+}
+
+impl Settings {
+    pub fn new(game_state: &GameState) -> Self {
+        let (generator, wall_density) = match game_state.generator {
+            Generator::Random { wall_density } => (GeneratorChoice::Random, wall_density as f32),
+            Generator::Backtracker => (GeneratorChoice::Backtracker, 0.2),
+        };
+
+        Settings {
+            width: game_state.width.to_string(),
+            height: game_state.height.to_string(),
+            generator,
+            wall_density,
+            seed: String::new(),
+            view_range: game_state.view_range.to_string(),
+            step_limit: game_state
+                .max_steps
+                .map(|max_steps| max_steps.to_string())
+                .unwrap_or_default(),
+            doors: game_state.doors.to_string(),
+            teleporters: game_state.teleporters.len().to_string(),
+            terrain: game_state.terrain,
+            slippery: game_state.slippery.to_string(),
+            hazards: game_state.hazards.to_string(),
+            hazards_visible: game_state.hazards_visible,
+            health: game_state.start.health.to_string(),
+            enemies: game_state.start_enemies.len().to_string(),
+            dynamic_walls: game_state.dynamic_walls.len().to_string(),
+            goals: game_state.goal_count().to_string(),
+            goal_mode: game_state.goal_mode,
+            items: game_state.items.len().to_string(),
+            end_condition: game_state.end_condition,
+            error: None,
+        }
+    }
+
+    pub fn builder(&self) -> Result<GameStateBuilder, String> {
+        fn number<T: std::str::FromStr>(name: &str, value: &str) -> Result<T, String> {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| format!("{name} must be a whole number"))
+        }
+
+        fn optional<T: std::str::FromStr>(name: &str, value: &str) -> Result<Option<T>, String> {
+            let value = value.trim();
+            if value.is_empty() {
+                return Ok(None);
+            }
+            value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("{name} must be a whole number or empty"))
+        }
+
+        let generator = match self.generator {
+            GeneratorChoice::Random => Generator::Random {
+                wall_density: self.wall_density as f64,
+            },
+            GeneratorChoice::Backtracker => Generator::Backtracker,
+        };
+
+        let mut builder = GameState::builder()
+            .size(
+                number("Width", &self.width)?,
+                number("Height", &self.height)?,
+            )
+            .generator(generator)
+            .view_range(number("View range", &self.view_range)?)
+            .max_steps(optional("Step limit", &self.step_limit)?)
+            .doors(number("Key/door pairs", &self.doors)?)
+            .teleporters(number("Teleporter pairs", &self.teleporters)?)
+            .terrain(self.terrain)
+            .slippery(number("Ice patches and belts", &self.slippery)?)
+            .hazards(number("Pits and spikes", &self.hazards)?)
+            .hazards_visible(self.hazards_visible)
+            .health(number("Health", &self.health)?)
+            .enemies(number("Enemies", &self.enemies)?)
+            .dynamic_walls(number("Dynamic walls", &self.dynamic_walls)?)
+            .goals(number("Goals", &self.goals)?)
+            .goal_mode(self.goal_mode)
+            .items(number("Coins and gems", &self.items)?)
+            .end_condition(self.end_condition);
+        if let Some(seed) = optional("Seed", &self.seed)? {
+            builder = builder.seed(seed);
+        }
+
+        Ok(builder)
+    }
+}
+
+pub struct SettingsView;
+
+impl SettingsView {
+    pub fn view<'a>(settings: &'a Settings, game_state: &GameState) -> Column<'a, Message> {
+        let labelled = |label: &'a str, input| column![text(label).size(12), input];
+
+        let mut panel = column![
+            text("Settings").size(20),
+            labelled(
+                "Width",
+                text_input("50", &settings.width)
+                    .on_input(Message::WidthChanged)
+                    .width(INPUT_WIDTH)
+            ),
+            labelled(
+                "Height",
+                text_input("30", &settings.height)
+                    .on_input(Message::HeightChanged)
+                    .width(INPUT_WIDTH)
+            ),
+            column![
+                text("Generator").size(12),
+                pick_list(
+                    GeneratorChoice::ALL,
+                    Some(settings.generator),
+                    Message::GeneratorSelected
+                ),
+            ],
+        ]
+        .spacing(10)
+        .width(200);
+
+        if settings.generator == GeneratorChoice::Random {
+            panel = panel.push(column![
+                text(format!("Wall density {:.2}", settings.wall_density)).size(12),
+                slider(
+                    0.0..=1.0,
+                    settings.wall_density,
+                    Message::WallDensityChanged
+                )
+                .step(0.05),
+            ]);
+        }
+
+        panel
+            .push(labelled(
+                "Seed (empty for random)",
+                text_input("random", &settings.seed)
+                    .on_input(Message::SeedChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(text(format!("Current seed: {}", game_state.seed)).size(12))
+            .push(labelled(
+                "View range",
+                text_input("3", &settings.view_range)
+                    .on_input(Message::ViewRangeChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Step limit (empty for none)",
+                text_input("none", &settings.step_limit)
+                    .on_input(Message::StepLimitChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Key/door pairs",
+                text_input("0", &settings.doors)
+                    .on_input(Message::DoorsChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Teleporter pairs",
+                text_input("0", &settings.teleporters)
+                    .on_input(Message::TeleportersChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(
+                checkbox(settings.terrain)
+                    .label("Mud, water and road")
+                    .on_toggle(Message::TerrainToggled),
+            )
+            .push(labelled(
+                "Ice patches and belts",
+                text_input("0", &settings.slippery)
+                    .on_input(Message::SlipperyChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Pits and spikes",
+                text_input("0", &settings.hazards)
+                    .on_input(Message::HazardsChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(
+                checkbox(settings.hazards_visible)
+                    .label("Agents see hazards")
+                    .on_toggle(Message::HazardsVisibleToggled),
+            )
+            .push(labelled(
+                "Health",
+                text_input("3", &settings.health)
+                    .on_input(Message::HealthChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Enemies",
+                text_input("0", &settings.enemies)
+                    .on_input(Message::EnemiesChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Dynamic walls",
+                text_input("0", &settings.dynamic_walls)
+                    .on_input(Message::DynamicWallsChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(labelled(
+                "Goals",
+                text_input("1", &settings.goals)
+                    .on_input(Message::GoalsChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(column![
+                text("Goal mode").size(12),
+                pick_list(
+                    GoalMode::ALL,
+                    Some(settings.goal_mode),
+                    Message::GoalModeSelected
+                ),
+            ])
+            .push(labelled(
+                "Coins and gems",
+                text_input("0", &settings.items)
+                    .on_input(Message::ItemsChanged)
+                    .width(INPUT_WIDTH),
+            ))
+            .push(column![
+                text("End condition").size(12),
+                pick_list(
+                    EndCondition::ALL,
+                    Some(settings.end_condition),
+                    Message::EndConditionSelected
+                ),
+            ])
+            .push(button("Regenerate").on_press(Message::Regenerate))
+            .push(
+                settings
+                    .error
+                    .as_deref()
+                    .map(|error| text(error).color(Color::from_rgb(0.8, 0.0, 0.0))),
+            )
+    }
+}
@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use iced::widget::canvas::{self, Cache};
+use iced::widget::{Column, button, column, pick_list, row, slider, text};
+use iced::{Color, Point, Renderer, Size};
+use logic::{GameState, NodeState, Position, Search, SearchAlgorithm, SearchStatus};
+
+use crate::camera::Viewport;
+use crate::controls_view::Message;
+
+const DEFAULT_SPEED: f32 = 20.0;
+const MAX_SPEED: f32 = 1000.0;
+// Faster speeds take several steps per tick instead of ticking faster than the screen
+const MIN_TICK: Duration = Duration::from_millis(16);
+
+#[derive(Debug)]
+pub struct Solver {
+    pub algorithm: SearchAlgorithm,
+    pub search: Option<Search>,
+    pub running: bool,
+    // Steps per second
+    pub speed: f32,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Solver {
+            algorithm: SearchAlgorithm::BreadthFirst,
+            search: None,
+            running: false,
+            speed: DEFAULT_SPEED,
+        }
+    }
+}
+
+impl Solver {
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.speed).max(MIN_TICK)
+    }
+
+    fn steps_per_tick(&self) -> usize {
+        (self.speed * self.tick_interval().as_secs_f32())
+            .round()
+            .max(1.0) as usize
+    }
+
+    pub fn play(&mut self, game_state: &GameState) {
+        if self
+            .search
+            .as_ref()
+            .is_none_or(|search| search.status() != SearchStatus::Running)
+        {
+            self.search = Some(Search::new(game_state, self.algorithm));
+        }
+        self.running = true;
+    }
+
+    pub fn step(&mut self, game_state: &GameState) {
+        let search = self
+            .search
+            .get_or_insert_with(|| Search::new(game_state, self.algorithm));
+        search.step(game_state);
+        self.running = false;
+    }
+
+    pub fn tick(&mut self, game_state: &GameState) {
+        let steps = self.steps_per_tick();
+        let Some(search) = &mut self.search else {
+            self.running = false;
+            return;
+        };
+
+        for _ in 0..steps {
+            if search.step(game_state) != SearchStatus::Running {
+                self.running = false;
+                break;
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.search = None;
+        self.running = false;
+    }
+}
+
+pub struct SolverView;
+
+impl SolverView {
+    pub fn view(solver: &Solver) -> Column<'_, Message> {
+        let play_pause = if solver.running {
+            button("Pause").on_press(Message::SolverPause)
+        } else {
+            button("Play").on_press(Message::SolverPlay)
+        };
+
+        let status = solver.search.as_ref().map(|search| {
+            let status = match search.status() {
+                SearchStatus::Running => "running".to_string(),
+                SearchStatus::Found => format!("path of {} tiles", search.path().len()),
+                SearchStatus::Exhausted => "no path".to_string(),
+            };
+            text(format!("{} expansions, {status}", search.expansions())).size(12)
+        });
+
+        column![
+            text("Solver").size(20),
+            pick_list(
+                SearchAlgorithm::ALL,
+                Some(solver.algorithm),
+                Message::SolverSelected
+            ),
+            row![
+                play_pause,
+                button("Step").on_press(Message::SolverStep),
+                button("Clear").on_press(Message::SolverClear),
+            ]
+            .spacing(5),
+            text(format!("Speed {:.0} steps/s", solver.speed)).size(12),
+            slider(1.0..=MAX_SPEED, solver.speed, Message::SolverSpeedChanged),
+        ]
+        .push(status)
+        .spacing(5)
+        .width(200)
+    }
+
+    pub fn draw(
+        solver: &Solver,
+        renderer: &Renderer,
+        bounds_size: Size,
+        viewport: &Viewport,
+    ) -> Option<canvas::Geometry> {
+        let search = solver.search.as_ref()?;
+
+        Some(Cache::default().draw(renderer, bounds_size, |frame| {
+            let nodes = search.nodes();
+            for y in viewport.visible_rows() {
+                for x in viewport.visible_columns() {
+                    let color = match nodes[Position { x, y }] {
+                        NodeState::Unseen => continue,
+                        NodeState::Frontier => Color::from_rgba(1.0, 0.5, 0.0, 0.6),
+                        NodeState::Visited => Color::from_rgba(0.3, 0.5, 1.0, 0.4),
+                    };
+                    frame.fill_rectangle(viewport.tile_origin(x, y), viewport.tile_size(), color);
+                }
+            }
+
+            // Drawn inset so the tiles underneath stay recognisable
+            let inset = viewport.scale * 0.25;
+            let path_size = Size::new(viewport.scale - 2.0 * inset, viewport.scale - 2.0 * inset);
+            for position in search.path() {
+                let origin = viewport.tile_origin(position.x, position.y);
+                frame.fill_rectangle(
+                    Point::new(origin.x + inset, origin.y + inset),
+                    path_size,
+                    Color::from_rgb(0.6, 0.0, 0.8),
+                );
+            }
+        }))
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use iced::widget::canvas::{self, Cache, Path};
+use iced::widget::{Column, checkbox, column, slider, text};
+use iced::{Color, Point, Renderer, Size};
+use logic::{Direction, GameState, Position};
+
+use crate::camera::Viewport;
+use crate::controls_view::Message;
+
+const DEFAULT_LENGTH: usize = 50;
+const MAX_LENGTH: usize = 1000;
+const TRAIL_RATIO: f32 = 0.4;
+
+#[derive(Debug)]
+pub struct Trail {
+    pub enabled: bool,
+    pub fading: bool,
+    pub length: usize,
+    // Oldest first, each with the direction the player turned to while there
+    points: VecDeque<(Position, Option<Direction>)>,
+}
+
+impl Trail {
+    pub fn new(game_state: &GameState) -> Self {
+        let mut trail = Trail {
+            enabled: false,
+            fading: true,
+            length: DEFAULT_LENGTH,
+            points: VecDeque::new(),
+        };
+        trail.restart(game_state);
+        trail
+    }
+
+    pub fn restart(&mut self, game_state: &GameState) {
+        self.points.clear();
+        self.points.push_back((game_state.player.position, None));
+    }
+
+    // Call after every action with where the player was before it
+    pub fn record(
+        &mut self,
+        game_state: &GameState,
+        previous: Position,
+        previous_direction: Direction,
+    ) {
+        let player = &game_state.player;
+        if player.position != previous {
+            self.points.push_back((player.position, None));
+        } else if player.direction != previous_direction
+            && let Some((_, turned)) = self.points.back_mut()
+        {
+            *turned = Some(player.direction);
+        }
+
+        self.truncate();
+    }
+
+    // Steps back over a move that was undone, turns are left as they were
+    pub fn undo(&mut self, game_state: &GameState) {
+        if self.points.len() > 1
+            && self
+                .points
+                .back()
+                .is_some_and(|&(position, _)| position != game_state.player.position)
+        {
+            self.points.pop_back();
+        }
+    }
+
+    pub fn set_length(&mut self, length: usize) {
+        self.length = length;
+        self.truncate();
+    }
+
+    fn truncate(&mut self) {
+        while self.points.len() > self.length.max(1) {
+            self.points.pop_front();
+        }
+    }
+}
+
+pub struct TrailView;
+
+impl TrailView {
+    pub fn view(trail: &Trail) -> Column<'_, Message> {
+        column![
+            checkbox(trail.enabled)
+                .label("Trail")
+                .on_toggle(Message::ShowTrail),
+            checkbox(trail.fading)
+                .label("Fade trail")
+                .on_toggle(Message::FadeTrail),
+            text(format!("Trail length {}", trail.length)).size(12),
+            slider(1.0..=MAX_LENGTH as f32, trail.length as f32, |length| {
+                Message::TrailLengthChanged(length as usize)
+            }),
+        ]
+        .spacing(5)
+        .width(200)
+    }
+
+    pub fn draw(
+        trail: &Trail,
+        renderer: &Renderer,
+        bounds_size: Size,
+        viewport: &Viewport,
+    ) -> canvas::Geometry {
+        Cache::default().draw(renderer, bounds_size, |frame| {
+            let columns = viewport.visible_columns();
+            let rows = viewport.visible_rows();
+            let scale = viewport.scale;
+            let count = trail.points.len() as f32;
+
+            for (age, &(position, turned)) in trail.points.iter().enumerate() {
+                if !columns.contains(&position.x) || !rows.contains(&position.y) {
+                    continue;
+                }
+
+                let alpha = if trail.fading {
+                    (age + 1) as f32 / count
+                } else {
+                    1.0
+                };
+                let origin = viewport.tile_origin(position.x, position.y);
+                let center = Point::new(origin.x + scale / 2.0, origin.y + scale / 2.0);
+
+                match turned {
+                    Some(direction) => frame.fill(
+                        &arrow(center, scale / 2.0, direction),
+                        Color::from_rgba(0.9, 0.4, 0.0, alpha),
+                    ),
+                    None => {
+                        let size = scale * TRAIL_RATIO;
+                        frame.fill_rectangle(
+                            Point::new(center.x - size / 2.0, center.y - size / 2.0),
+                            Size::new(size, size),
+                            Color::from_rgba(0.2, 0.4, 1.0, alpha),
+                        );
+                    }
+                }
+            }
+        })
+    }
+}
+
+// Triangle pointing the way the player turned
+fn arrow(center: Point, radius: f32, direction: Direction) -> Path {
+    let (dx, dy) = direction.delta();
+    let (dx, dy) = (dx as f32, dy as f32);
+    // Perpendicular to the direction, for the base of the triangle
+    let (px, py) = (-dy, dx);
+
+    Path::new(|builder| {
+        builder.move_to(Point::new(center.x + dx * radius, center.y + dy * radius));
+        builder.line_to(Point::new(
+            center.x - dx * radius / 2.0 + px * radius * 0.7,
+            center.y - dy * radius / 2.0 + py * radius * 0.7,
+        ));
+        builder.line_to(Point::new(
+            center.x - dx * radius / 2.0 - px * radius * 0.7,
+            center.y - dy * radius / 2.0 - py * radius * 0.7,
+        ));
+        builder.close();
+    })
+}
@@ -0,0 +1,139 @@
+use std::time::{Duration, Instant};
+
+use iced::widget::{button, column, container, opaque, row, text};
+use iced::{Color, Element, Task};
+use logic::{EndCondition, GameState, Outcome, cheapest_actions, optimal_actions};
+
+use crate::background;
+use crate::controls_view::Message;
+
+// Timing and the optimal baseline for the episode being played
+#[derive(Debug, Default)]
+pub struct Episode {
+    started: Option<Instant>,
+    elapsed: Option<Duration>,
+    optimal_steps: Option<usize>,
+    cheapest_cost: Option<usize>,
+    // Counts restarts, so a baseline searched for an earlier run is dropped
+    run: u64,
+}
+
+impl Episode {
+    pub fn restart(&mut self) {
+        *self = Episode {
+            run: self.run + 1,
+            ..Episode::default()
+        };
+    }
+
+    // The clock starts with the first action rather than when the maze appears,
+    // and the baseline is searched for in the background from there
+    pub fn before_action(&mut self, game_state: &GameState) -> Task<Message> {
+        if self.started.is_some() {
+            return Task::none();
+        }
+        self.started = Some(Instant::now());
+
+        let run = self.run;
+        let game_state = game_state.clone();
+        background::search(move || {
+            let optimal_steps = optimal_actions(&game_state).map(|actions| actions.len());
+            let cheapest_cost = cheapest_actions(&game_state).map(|(_, cost)| cost);
+            (optimal_steps, cheapest_cost)
+        })
+        .map(move |(optimal_steps, cheapest_cost)| {
+            Message::BaselineFound(run, optimal_steps, cheapest_cost)
+        })
+    }
+
+    pub fn baseline_found(
+        &mut self,
+        run: u64,
+        optimal_steps: Option<usize>,
+        cheapest_cost: Option<usize>,
+    ) {
+        if run == self.run {
+            self.optimal_steps = optimal_steps;
+            self.cheapest_cost = cheapest_cost;
+        }
+    }
+
+    // An undone finish keeps the clock running
+    pub fn after_undo(&mut self, game_state: &GameState) {
+        if !game_state.is_over() {
+            self.elapsed = None;
+        }
+    }
+
+    pub fn after_action(&mut self, game_state: &GameState) {
+        if game_state.is_over() && self.elapsed.is_none() {
+            self.elapsed = self.started.map(|started| started.elapsed());
+        }
+    }
+}
+
+pub struct VictoryView;
+
+impl VictoryView {
+    pub fn view<'a>(game_state: &GameState, episode: &Episode) -> Option<Element<'a, Message>> {
+        let title = match game_state.outcome {
+            Outcome::InProgress => return None,
+            Outcome::Success => text(match game_state.end_condition {
+                EndCondition::Goal => "Goal reached!",
+                EndCondition::CollectAll => "Everything collected!",
+            })
+            .size(28)
+            .color(Color::from_rgb(0.0, 0.6, 0.0)),
+            Outcome::Died => text("You died")
+                .size(28)
+                .color(Color::from_rgb(0.8, 0.0, 0.0)),
+            Outcome::TimedOut => text("Out of steps")
+                .size(28)
+                .color(Color::from_rgb(0.8, 0.0, 0.0)),
+        };
+
+        let elapsed = episode.elapsed.unwrap_or_default().as_secs_f64();
+        let optimal = episode
+            .optimal_steps
+            .map(|steps| steps.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let cheapest = episode
+            .cheapest_cost
+            .map(|cost| cost.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        // Only a finished run can be compared with the optimal one
+        let efficiency = match (game_state.outcome, episode.optimal_steps) {
+            (Outcome::Success, Some(optimal)) => {
+                format!(
+                    "{:.0}%",
+                    optimal as f64 / game_state.steps.max(1) as f64 * 100.0
+                )
+            }
+            _ => "-".to_string(),
+        };
+
+        let panel = container(
+            column![
+                title,
+                text(format!("Time: {elapsed:.1}s")),
+                text(format!("Steps: {}", game_state.steps)),
+                text(format!("Turns: {}", game_state.turns)),
+                text(format!("Optimal steps: {optimal}")),
+                text(format!("Cost: {}", game_state.cost)),
+                text(format!("Cheapest cost: {cheapest}")),
+                text(format!("Efficiency: {efficiency}")),
+                text(format!("Score: {}", game_state.score())),
+                row![
+                    button("Play again").on_press(Message::PlayAgain),
+                    button("New maze").on_press(Message::Reset),
+                ]
+                .spacing(10),
+            ]
+            .spacing(8),
+        )
+        .padding(20)
+        .style(container::rounded_box);
+
+        Some(opaque(panel))
+    }
+}